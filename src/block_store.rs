@@ -0,0 +1,156 @@
+//! Stores of blocks addressed by [`Cid`], which validation fetches the targets of links from
+
+use std::{fmt, io, path::PathBuf};
+
+use crate::{
+    cid::{self, CidError},
+    codec, Cid, Value,
+};
+
+/// Source of the blocks links point to
+pub trait BlockStore {
+    /// Bytes of the block `cid` identifies, or `None` if the store doesn't have it
+    ///
+    /// The bytes aren't checked against the CID; [`load`] does that.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the store has the block but can't read it.
+    fn get(&self, cid: &Cid) -> io::Result<Option<Vec<u8>>>;
+}
+
+/// A directory holding each block in a file named by the string form of its CID
+#[derive(Clone, Debug)]
+pub struct DirectoryStore {
+    dir: PathBuf,
+}
+
+impl DirectoryStore {
+    /// Creates a store of the blocks in `dir`, which isn't read until blocks are fetched
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Writes a block encoded with the `codec` multicodec, returning its sha2-256 version 1 CID
+    ///
+    /// # Errors
+    ///
+    /// Fails if the block's file can't be written.
+    pub fn put(&self, codec: u64, block: &[u8]) -> io::Result<Cid> {
+        let cid = Cid::new_v1(codec, block);
+        std::fs::write(self.path(&cid), block)?;
+        Ok(cid)
+    }
+
+    fn path(&self, cid: &Cid) -> PathBuf {
+        self.dir.join(cid.to_string())
+    }
+}
+
+impl BlockStore for DirectoryStore {
+    fn get(&self, cid: &Cid) -> io::Result<Option<Vec<u8>>> {
+        match std::fs::read(self.path(cid)) {
+            Ok(block) => Ok(Some(block)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Why a stored block couldn't be loaded as a value
+#[derive(Debug)]
+pub enum BlockError {
+    /// The store has the block but couldn't read it
+    Io(io::Error),
+    /// The block's hash doesn't match its CID, or uses an unsupported hash function
+    Cid(CidError),
+    /// The CID's multicodec is none of DAG-CBOR, DAG-JSON, or raw
+    UnsupportedCodec(u64),
+    /// The block isn't valid in its codec
+    Decode(String),
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Io(err) => write!(f, "{}", err),
+            Self::Cid(err) => write!(f, "{}", err),
+            Self::UnsupportedCodec(code) => write!(f, "codec 0x{:x} isn't supported", code),
+            Self::Decode(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for BlockError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Cid(err) => Some(err),
+            Self::UnsupportedCodec(_) | Self::Decode(_) => None,
+        }
+    }
+}
+
+/// Fetches the block `cid` identifies, checks its hash, and decodes it with the codec the CID names
+///
+/// DAG-CBOR blocks must be in canonical form, as data files are. Returns `None` if the store doesn't have the block.
+///
+/// # Errors
+///
+/// Fails if the block can't be read, doesn't match its CID, or can't be decoded.
+pub fn load(store: &dyn BlockStore, cid: &Cid) -> Result<Option<Value>, BlockError> {
+    match store.get(cid).map_err(BlockError::Io)? {
+        Some(block) => decode(cid, block).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn decode(cid: &Cid, block: Vec<u8>) -> Result<Value, BlockError> {
+    cid.verify(&block).map_err(BlockError::Cid)?;
+    let value = match cid.codec() {
+        cid::DAG_CBOR => codec::dag_cbor::decode_strict(&block).map_err(|err| err.to_string()),
+        cid::DAG_JSON => codec::dag_json::decode(&block).map_err(|err| err.to_string()),
+        cid::RAW => Ok(Value::Bytes(block)),
+        code => return Err(BlockError::UnsupportedCodec(code)),
+    };
+    value.map_err(BlockError::Decode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_verified_blocks_from_a_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DirectoryStore::new(dir.path());
+        let cid = store.put(cid::DAG_JSON, b"[1]").unwrap();
+
+        assert_eq!(
+            load(&store, &cid).unwrap(),
+            Some(Value::List(vec![Value::Int(1)]))
+        );
+        assert!(load(&store, &Cid::new_v1(cid::DAG_JSON, b"[2]"))
+            .unwrap()
+            .is_none());
+
+        std::fs::write(dir.path().join(cid.to_string()), b"[3]").unwrap();
+        assert!(matches!(
+            load(&store, &cid),
+            Err(BlockError::Cid(CidError::HashMismatch))
+        ));
+
+        // {"b": 1, "a": 2} with unsorted keys
+        let cid = store
+            .put(cid::DAG_CBOR, &[0xa2, 0x61, b'b', 0x01, 0x61, b'a', 0x02])
+            .unwrap();
+        assert!(matches!(load(&store, &cid), Err(BlockError::Decode(_))));
+
+        let cid = store.put(cid::DAG_PB, b"").unwrap();
+        assert!(matches!(
+            load(&store, &cid),
+            Err(BlockError::UnsupportedCodec(cid::DAG_PB))
+        ));
+    }
+}
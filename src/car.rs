@@ -0,0 +1,280 @@
+//! [CARv1](https://ipld.io/specs/transport/car/carv1/) archives, which bundle a DAG's blocks with the CIDs of its roots
+//!
+//! An archive starts with a varint-prefixed DAG-CBOR header `{"roots": [...], "version": 1}` followed by sections,
+//! each a varint length and then a block's binary CID and bytes.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt, io,
+};
+
+use crate::{
+    block_store::{self, BlockStore},
+    cid::CidError,
+    codec, varint, Cid, Value,
+};
+
+/// The roots and blocks of a CAR archive
+#[derive(Clone, Debug, PartialEq)]
+pub struct Car {
+    roots: Vec<Cid>,
+    /// Blocks in the order they appear in the archive
+    blocks: Vec<(Cid, Vec<u8>)>,
+    /// Position of each CID's first block in `blocks`
+    index: BTreeMap<Cid, usize>,
+}
+
+/// Why bytes aren't a CAR archive
+#[derive(Clone, Debug, PartialEq)]
+pub enum CarError {
+    /// The header isn't a DAG-CBOR map of version 1 with a list of root links
+    InvalidHeader(String),
+    /// The archive ends partway through a length or section
+    Truncated {
+        /// Position of the length or section within the archive
+        offset: usize,
+    },
+    /// A section doesn't start with a binary CID
+    InvalidCid {
+        /// Position of the section within the archive
+        offset: usize,
+        /// Why the section's start isn't a CID
+        source: CidError,
+    },
+}
+
+impl fmt::Display for CarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::InvalidHeader(message) => write!(f, "invalid CAR header: {}", message),
+            Self::Truncated { offset } => write!(f, "CAR is truncated at byte {}", offset),
+            Self::InvalidCid { offset, source } => {
+                write!(
+                    f,
+                    "section at byte {} has an invalid CID: {}",
+                    offset, source
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CarError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidCid { source, .. } => Some(source),
+            Self::InvalidHeader(_) | Self::Truncated { .. } => None,
+        }
+    }
+}
+
+impl Car {
+    /// An archive of the given roots without any blocks yet
+    #[must_use]
+    pub fn new(roots: Vec<Cid>) -> Self {
+        Self {
+            roots,
+            blocks: vec![],
+            index: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a block, which is written after those already added
+    pub fn insert(&mut self, cid: Cid, block: Vec<u8>) {
+        self.index.entry(cid.clone()).or_insert(self.blocks.len());
+        self.blocks.push((cid, block));
+    }
+
+    /// Reads a CAR version 1 archive
+    ///
+    /// Blocks aren't checked against their CIDs until they're fetched with [`block_store::load`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the header isn't a version 1 CAR header, or if a section is truncated or lacks a valid CID.
+    pub fn read(bytes: &[u8]) -> Result<Self, CarError> {
+        let (header, mut rest) = section(bytes, 0)?;
+        let roots = roots(
+            &codec::dag_cbor::decode(header)
+                .map_err(|err| CarError::InvalidHeader(err.to_string()))?,
+        )?;
+
+        let mut car = Self::new(roots);
+        while !rest.is_empty() {
+            let offset = bytes.len() - rest.len();
+            let (section, after) = section(rest, offset)?;
+            let (cid, block) =
+                Cid::read(section).map_err(|source| CarError::InvalidCid { offset, source })?;
+            car.insert(cid, block.to_vec());
+            rest = after;
+        }
+        Ok(car)
+    }
+
+    /// Writes the archive in CAR version 1 format
+    ///
+    /// # Errors
+    ///
+    /// Fails if writing to `out` fails.
+    pub fn write<W: io::Write>(&self, out: &mut W) -> io::Result<()> {
+        let mut header = BTreeMap::new();
+        header.insert(
+            "roots".to_string(),
+            Value::List(self.roots.iter().cloned().map(Value::Link).collect()),
+        );
+        header.insert("version".to_string(), Value::Int(1));
+        let header = codec::dag_cbor::encode(&Value::Map(header))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        let mut bytes = vec![];
+        write_section(&mut bytes, &[&header]);
+        for (cid, block) in &self.blocks {
+            write_section(&mut bytes, &[&cid.to_bytes(), block]);
+        }
+        out.write_all(&bytes)
+    }
+
+    /// CIDs of the blocks the archive's DAG starts from
+    #[must_use]
+    pub fn roots(&self) -> &[Cid] {
+        &self.roots
+    }
+
+    /// CIDs of the archive's blocks in the order they appear, including any repeats
+    pub fn cids(&self) -> impl Iterator<Item = &Cid> {
+        self.blocks.iter().map(|(cid, _)| cid)
+    }
+
+    /// CIDs of blocks no chain of links from a root reaches, in the order they appear
+    ///
+    /// Blocks which can't be loaded are treated as linking to nothing.
+    #[must_use]
+    pub fn unreachable(&self) -> Vec<&Cid> {
+        let mut reached = BTreeSet::new();
+        let mut pending = self.roots.clone();
+        while let Some(cid) = pending.pop() {
+            if let Ok(Some(value)) = block_store::load(self, &cid) {
+                pending.extend(
+                    value
+                        .links()
+                        .into_iter()
+                        .filter(|link| !reached.contains(*link))
+                        .cloned(),
+                );
+            }
+            reached.insert(cid);
+        }
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter(|(i, (cid, _))| self.index[cid] == *i && !reached.contains(cid))
+            .map(|(_, (cid, _))| cid)
+            .collect()
+    }
+}
+
+impl BlockStore for Car {
+    fn get(&self, cid: &Cid) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.index.get(cid).map(|&i| self.blocks[i].1.clone()))
+    }
+}
+
+/// Splits a varint-prefixed section from the start of `bytes`, which begin at `offset` within the archive
+fn section(bytes: &[u8], offset: usize) -> Result<(&[u8], &[u8]), CarError> {
+    varint::read(bytes)
+        .and_then(|(len, rest)| {
+            let len = usize::try_from(len).ok()?;
+            (len <= rest.len()).then(|| rest.split_at(len))
+        })
+        .ok_or(CarError::Truncated { offset })
+}
+
+fn write_section(out: &mut Vec<u8>, parts: &[&[u8]]) {
+    let len: usize = parts.iter().map(|part| part.len()).sum();
+    varint::write(u64::try_from(len).unwrap_or(u64::MAX), out);
+    for part in parts {
+        out.extend_from_slice(part);
+    }
+}
+
+fn roots(header: &Value) -> Result<Vec<Cid>, CarError> {
+    let invalid = |message: &str| CarError::InvalidHeader(message.to_string());
+    let entries = match header {
+        Value::Map(entries) if entries.get("version") == Some(&Value::Int(1)) => entries,
+        Value::Map(_) => return Err(invalid("version isn't 1")),
+        _ => return Err(invalid("header isn't a map")),
+    };
+    match entries.get("roots") {
+        Some(Value::List(roots)) => roots
+            .iter()
+            .map(|root| match root {
+                Value::Link(cid) => Ok(cid.clone()),
+                _ => Err(invalid("roots contains a value which isn't a link")),
+            })
+            .collect(),
+        _ => Err(invalid("roots isn't a list")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cid;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn roundtrips_and_finds_unreachable_blocks() {
+        let leaf = br#"{"n": 1}"#.to_vec();
+        let leaf_cid = Cid::new_v1(cid::DAG_JSON, &leaf);
+        let root = format!(r#"[{{"/": "{}"}}]"#, leaf_cid).into_bytes();
+        let root_cid = Cid::new_v1(cid::DAG_JSON, &root);
+        let stray = b"stray".to_vec();
+        let stray_cid = Cid::new_v1(cid::RAW, &stray);
+
+        let mut car = Car::new(vec![root_cid.clone()]);
+        car.insert(root_cid.clone(), root);
+        car.insert(stray_cid.clone(), stray);
+        car.insert(leaf_cid.clone(), leaf.clone());
+        let mut bytes = vec![];
+        car.write(&mut bytes).unwrap();
+
+        let car = Car::read(&bytes).unwrap();
+        assert_eq!(car.roots(), [root_cid.clone()]);
+        assert_eq!(
+            car.cids().collect::<Vec<_>>(),
+            [&root_cid, &stray_cid, &leaf_cid]
+        );
+        assert_eq!(car.get(&leaf_cid).unwrap(), Some(leaf));
+        assert_eq!(car.unreachable(), [&stray_cid]);
+    }
+
+    #[test]
+    fn rejects_malformed_archives() {
+        assert_eq!(Car::read(&[]), Err(CarError::Truncated { offset: 0 }));
+        assert_eq!(
+            Car::read(&[0x01, 0xa0]).unwrap_err().to_string(),
+            "invalid CAR header: version isn't 1"
+        );
+
+        let mut bytes = vec![];
+        Car::new(vec![]).write(&mut bytes).unwrap();
+        let header_len = bytes.len();
+        bytes.extend_from_slice(&[0x02, 0x02, 0x00]);
+        assert_eq!(
+            Car::read(&bytes),
+            Err(CarError::InvalidCid {
+                offset: header_len,
+                source: CidError::UnsupportedVersion(2)
+            })
+        );
+        bytes.truncate(header_len);
+        bytes.extend_from_slice(&[0x05, 0x01]);
+        assert_eq!(
+            Car::read(&bytes),
+            Err(CarError::Truncated { offset: header_len })
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! [Content identifiers](https://github.com/multiformats/cid), which links use to name the blocks they point to
+//!
+//! A CID combines a version, a [multicodec](https://github.com/multiformats/multicodec) saying how the block is
+//! encoded, and a [multihash](https://github.com/multiformats/multihash) of the block's bytes. Version 0 CIDs are
+//! always sha2-256 hashes of `dag-pb` blocks, written in base58 without a multibase prefix.
+
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+use sha2::{Digest, Sha256};
+
+use crate::{multibase, varint};
+
+/// Multicodec of `dag-pb` blocks, the only codec version 0 CIDs can name
+pub const DAG_PB: u64 = 0x70;
+/// Multicodec of DAG-CBOR blocks
+pub const DAG_CBOR: u64 = 0x71;
+/// Multicodec of DAG-JSON blocks
+pub const DAG_JSON: u64 = 0x0129;
+/// Multicodec of blocks which are opaque bytes
+pub const RAW: u64 = 0x55;
+
+/// Multihash code of the identity "hash", whose digest is the block itself
+pub const IDENTITY: u64 = 0x00;
+/// Multihash code of sha2-256
+pub const SHA2_256: u64 = 0x12;
+
+/// Content identifier of a block
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cid {
+    version: Version,
+    codec: u64,
+    hash_code: u64,
+    digest: Vec<u8>,
+}
+
+/// Version of a [`Cid`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Version {
+    /// A bare sha2-256 multihash of a DAG-PB block, written in base58 starting with `Qm`
+    V0,
+    /// Prefixed with its version and the block's multicodec, written in a multibase
+    V1,
+}
+
+/// Multibase a version 1 [`Cid`]'s string form is written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base {
+    /// Lowercase RFC 4648 base32, prefixed with `b`
+    Base32,
+    /// Base58 with the Bitcoin alphabet, prefixed with `z`
+    Base58Btc,
+}
+
+/// Why bytes or a string aren't a CID, or a block doesn't match its CID
+#[derive(Clone, Debug, PartialEq)]
+pub enum CidError {
+    /// A string starts with a multibase prefix other than `b` or `z`, or is empty
+    UnsupportedMultibase(Option<char>),
+    /// A string contains characters outside its multibase's alphabet
+    InvalidMultibase(String),
+    /// The binary form ends partway through
+    Truncated,
+    /// A varint in the binary form is too long or not minimally encoded
+    InvalidVarint,
+    /// The version is neither 0 nor 1
+    UnsupportedVersion(u64),
+    /// A digest's length is wrong for its hash function
+    DigestLength {
+        /// Length the hash function produces
+        expected: usize,
+        /// Length of the digest
+        found: usize,
+    },
+    /// Bytes follow the binary form of a CID
+    TrailingBytes,
+    /// A block can't be checked against its CID because its hash function isn't supported
+    UnsupportedHash(u64),
+    /// A block's hash isn't the digest in its CID
+    HashMismatch,
+}
+
+impl fmt::Display for CidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::UnsupportedMultibase(Some(prefix)) => {
+                write!(f, "multibase prefix {:?} isn't supported", prefix)
+            }
+            Self::UnsupportedMultibase(None) => write!(f, "CID is empty"),
+            Self::InvalidMultibase(text) => write!(f, "{:?} isn't valid multibase", text),
+            Self::Truncated => write!(f, "CID is truncated"),
+            Self::InvalidVarint => write!(f, "CID contains an invalid varint"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "CID version {} isn't supported", version)
+            }
+            Self::DigestLength { expected, found } => write!(
+                f,
+                "digest has {} bytes, its hash function produces {}",
+                found, expected
+            ),
+            Self::TrailingBytes => write!(f, "bytes follow the CID"),
+            Self::UnsupportedHash(code) => write!(f, "hash function 0x{:x} isn't supported", code),
+            Self::HashMismatch => write!(f, "block doesn't match its CID"),
+        }
+    }
+}
+
+impl std::error::Error for CidError {}
+
+impl Cid {
+    /// Version 1 CID of a block encoded with `codec`, hashed with sha2-256
+    #[must_use]
+    pub fn new_v1(codec: u64, block: &[u8]) -> Self {
+        Self {
+            version: Version::V1,
+            codec,
+            hash_code: SHA2_256,
+            digest: Sha256::digest(block).to_vec(),
+        }
+    }
+
+    /// Version 0 CID of a `dag-pb` block
+    #[must_use]
+    pub fn new_v0(block: &[u8]) -> Self {
+        Self {
+            version: Version::V0,
+            ..Self::new_v1(DAG_PB, block)
+        }
+    }
+
+    /// Version of the CID, which determines its binary and string forms
+    #[must_use]
+    pub const fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Multicodec of the block the CID identifies
+    #[must_use]
+    pub const fn codec(&self) -> u64 {
+        self.codec
+    }
+
+    /// Multihash code of the hash function which produced the digest
+    #[must_use]
+    pub const fn hash_code(&self) -> u64 {
+        self.hash_code
+    }
+
+    /// Hash of the block the CID identifies
+    #[must_use]
+    pub fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+
+    /// Parses the binary form of a CID from the start of `bytes`, returning it and the bytes after it
+    ///
+    /// # Errors
+    ///
+    /// Fails if `bytes` don't start with a version 0 or 1 CID whose digest length suits its hash function.
+    pub fn read(bytes: &[u8]) -> Result<(Self, &[u8]), CidError> {
+        if bytes.starts_with(&[0x12, 0x20]) {
+            let (hash, rest) = Multihash::read(bytes)?;
+            return Ok((
+                Self {
+                    version: Version::V0,
+                    codec: DAG_PB,
+                    hash_code: hash.code,
+                    digest: hash.digest,
+                },
+                rest,
+            ));
+        }
+        let (version, rest) = read_varint(bytes)?;
+        if version != 1 {
+            return Err(CidError::UnsupportedVersion(version));
+        }
+        let (codec, rest) = read_varint(rest)?;
+        let (hash, rest) = Multihash::read(rest)?;
+        Ok((
+            Self {
+                version: Version::V1,
+                codec,
+                hash_code: hash.code,
+                digest: hash.digest,
+            },
+            rest,
+        ))
+    }
+
+    /// Parses the binary form of a CID
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`Cid::read`], or if bytes follow the CID.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CidError> {
+        match Self::read(bytes)? {
+            (cid, []) => Ok(cid),
+            _ => Err(CidError::TrailingBytes),
+        }
+    }
+
+    /// Binary form of the CID, as embedded in DAG-CBOR links and CAR files
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![];
+        if self.version == Version::V1 {
+            varint::write(1, &mut out);
+            varint::write(self.codec, &mut out);
+        }
+        varint::write(self.hash_code, &mut out);
+        varint::write(
+            u64::try_from(self.digest.len()).unwrap_or(u64::MAX),
+            &mut out,
+        );
+        out.extend_from_slice(&self.digest);
+        out
+    }
+
+    /// String form of the CID in the given multibase
+    ///
+    /// Version 0 CIDs are always written in base58 without a prefix, whichever base is asked for.
+    #[must_use]
+    pub fn to_string_of_base(&self, base: Base) -> String {
+        let bytes = self.to_bytes();
+        match (self.version, base) {
+            (Version::V0, _) => multibase::encode_base58(&bytes),
+            (Version::V1, Base::Base32) => format!("b{}", multibase::encode_base32(&bytes)),
+            (Version::V1, Base::Base58Btc) => format!("z{}", multibase::encode_base58(&bytes)),
+        }
+    }
+
+    /// Checks that `block` is the block the CID identifies by hashing it
+    ///
+    /// # Errors
+    ///
+    /// Fails if the block's hash differs from the CID's digest, or if the CID's hash function is neither sha2-256 nor
+    /// identity.
+    pub fn verify(&self, block: &[u8]) -> Result<(), CidError> {
+        let matches = match self.hash_code {
+            SHA2_256 => Sha256::digest(block)[..] == self.digest[..],
+            IDENTITY => block == self.digest.as_slice(),
+            code => return Err(CidError::UnsupportedHash(code)),
+        };
+        if matches {
+            Ok(())
+        } else {
+            Err(CidError::HashMismatch)
+        }
+    }
+}
+
+/// Parses a version 0 CID in base58, or a version 1 CID in multibase base32 or base58btc
+impl FromStr for Cid {
+    type Err = CidError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CidError::InvalidMultibase(s.to_string());
+        let bytes = if s.len() == 46 && s.starts_with("Qm") {
+            multibase::decode_base58(s).ok_or_else(invalid)?
+        } else {
+            match s.chars().next() {
+                Some('b') => multibase::decode_base32(&s[1..]).ok_or_else(invalid)?,
+                Some('z') => multibase::decode_base58(&s[1..]).ok_or_else(invalid)?,
+                prefix => return Err(CidError::UnsupportedMultibase(prefix)),
+            }
+        };
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// Writes version 0 CIDs in base58 and version 1 CIDs in base32
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.to_string_of_base(Base::Base32))
+    }
+}
+
+struct Multihash {
+    code: u64,
+    digest: Vec<u8>,
+}
+
+impl Multihash {
+    fn read(bytes: &[u8]) -> Result<(Self, &[u8]), CidError> {
+        let (code, rest) = read_varint(bytes)?;
+        let (len, rest) = read_varint(rest)?;
+        let len = usize::try_from(len).map_err(|_| CidError::Truncated)?;
+        if code == SHA2_256 && len != 32 {
+            return Err(CidError::DigestLength {
+                expected: 32,
+                found: len,
+            });
+        }
+        if rest.len() < len {
+            return Err(CidError::Truncated);
+        }
+        let (digest, rest) = rest.split_at(len);
+        Ok((
+            Self {
+                code,
+                digest: digest.to_vec(),
+            },
+            rest,
+        ))
+    }
+}
+
+fn read_varint(bytes: &[u8]) -> Result<(u64, &[u8]), CidError> {
+    varint::read(bytes).ok_or(if bytes.iter().all(|b| b & 0x80 != 0) {
+        CidError::Truncated
+    } else {
+        CidError::InvalidVarint
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn string_forms_roundtrip() {
+        let v1 = "bafyreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku";
+        let cid: Cid = v1.parse().unwrap();
+        assert_eq!(cid, Cid::new_v1(DAG_CBOR, b""));
+        assert_eq!(cid.to_string(), v1);
+        assert_eq!(
+            cid.to_string_of_base(Base::Base58Btc)
+                .parse::<Cid>()
+                .unwrap(),
+            cid
+        );
+
+        let v0 = "QmbWqxBEKC3P8tqsKc98xmWNzrzDtRLMiMPL8wBuTGsMnR";
+        let cid: Cid = v0.parse().unwrap();
+        assert_eq!(cid.version(), Version::V0);
+        assert_eq!(cid.codec(), DAG_PB);
+        assert_eq!(cid.to_string(), v0);
+        assert_eq!(Cid::from_bytes(&cid.to_bytes()).unwrap(), cid);
+    }
+
+    #[test]
+    fn rejects_malformed_cids() {
+        assert_eq!("".parse::<Cid>(), Err(CidError::UnsupportedMultibase(None)));
+        assert_eq!(
+            "fabc".parse::<Cid>(),
+            Err(CidError::UnsupportedMultibase(Some('f')))
+        );
+        assert_eq!(
+            "b0".parse::<Cid>(),
+            Err(CidError::InvalidMultibase("b0".to_string()))
+        );
+        assert_eq!(
+            Cid::from_bytes(&[0x02, 0x71]),
+            Err(CidError::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            Cid::from_bytes(&[0x01, 0x71, 0x12, 0x01, 0x00]),
+            Err(CidError::DigestLength {
+                expected: 32,
+                found: 1
+            })
+        );
+        assert_eq!(
+            Cid::from_bytes(&[0x01, 0x71, 0x00, 0x02, 0x00]),
+            Err(CidError::Truncated)
+        );
+        assert_eq!(
+            Cid::from_bytes(&[0x01, 0x71, 0x00, 0x00, 0x00]),
+            Err(CidError::TrailingBytes)
+        );
+    }
+
+    #[test]
+    fn verifies_blocks_against_their_digest() {
+        let cid = Cid::new_v1(RAW, b"block");
+        assert_eq!(cid.verify(b"block"), Ok(()));
+        assert_eq!(cid.verify(b"other"), Err(CidError::HashMismatch));
+
+        let identity = Cid::from_bytes(&[0x01, 0x55, 0x00, 0x02, b'h', b'i']).unwrap();
+        assert_eq!(identity.verify(b"hi"), Ok(()));
+        assert_eq!(
+            Cid::from_bytes(&[0x01, 0x55, 0x11, 0x01, 0x00])
+                .unwrap()
+                .verify(b""),
+            Err(CidError::UnsupportedHash(0x11))
+        );
+    }
+}
@@ -1,8 +1,14 @@
 #![cfg(feature = "build-binary")]
 
+use std::io::IsTerminal;
+
 use ipld_schema::{run, Opt};
 use structopt::StructOpt;
 
-fn main() -> Result<(), ()> {
-    run(Opt::from_args(), &mut std::io::stdout())
+fn main() {
+    if let Err(err) = run(Opt::from_args(), &mut std::io::stdout()) {
+        let color = std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        eprint!("{}", err.render(color));
+        std::process::exit(err.exit_code());
+    }
 }
@@ -0,0 +1,36 @@
+//! Encodings of Data Model [`Value`](crate::Value)s as bytes
+
+use std::{fmt, str::FromStr};
+
+pub mod dag_cbor;
+pub mod dag_json;
+
+/// Codec data files are read and written with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Codec {
+    /// [`dag_json`], written as `dag-json`
+    DagJson,
+    /// [`dag_cbor`], written as `dag-cbor`
+    DagCbor,
+}
+
+impl FromStr for Codec {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dag-json" => Ok(Self::DagJson),
+            "dag-cbor" => Ok(Self::DagCbor),
+            _ => Err("codec must be dag-json or dag-cbor"),
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::DagJson => write!(f, "dag-json"),
+            Self::DagCbor => write!(f, "dag-cbor"),
+        }
+    }
+}
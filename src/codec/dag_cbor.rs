@@ -0,0 +1,615 @@
+//! [DAG-CBOR](https://specs.ipld.io/block-layer/codecs/dag-cbor.html), a CBOR encoding of Data Model values
+//!
+//! Links are CBOR tag 42 wrapping a byte string of the binary CID prefixed with a `0x00` byte. Floats are always
+//! encoded in 64 bits, and map keys are strings sorted by length and then by their UTF-8 bytes, so equal values always
+//! encode identically. [`decode_strict`] accepts only that canonical form, while [`decode`] also accepts
+//! indefinite-length items, shorter floats, unsorted keys, and integers whose heads are longer than necessary.
+
+use std::{collections::BTreeMap, convert::TryFrom, fmt};
+
+use crate::{cid::CidError, Cid, Value};
+
+const LINK_TAG: u64 = 42;
+
+/// Deepest nesting of lists, maps and tags decoding accepts, which keeps untrusted input from exhausting the stack
+pub const MAX_NESTING: usize = 256;
+
+/// Why bytes couldn't be decoded as DAG-CBOR
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodeError {
+    /// Position of the offending item within the input
+    pub offset: usize,
+    /// What's wrong with the item
+    pub reason: DecodeReason,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "at byte {}: {}", self.offset, self.reason)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// What made DAG-CBOR input undecodable
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodeReason {
+    /// The input ends partway through an item
+    UnexpectedEnd,
+    /// Bytes follow the single top-level item
+    TrailingBytes,
+    /// A head uses additional information 28, 29, or 30, which CBOR reserves
+    Reserved(u8),
+    /// An integer or tag head uses additional information 31, which only items with a length may use
+    IndefiniteArgument(u8),
+    /// A "break" appears outside of an indefinite-length item
+    UnexpectedBreak,
+    /// A chunk of an indefinite-length string isn't a definite-length string of the same type
+    InvalidChunk,
+    /// A simple value other than `false`, `true`, or `null`, such as `undefined`
+    UnsupportedSimple(u8),
+    /// An integer is outside the range of [`Value::Int`]
+    IntOutOfRange(i128),
+    /// A text string isn't UTF-8
+    InvalidUtf8,
+    /// A map key isn't a text string
+    NonStringKey,
+    /// A tag other than 42
+    UnsupportedTag(u64),
+    /// Tag 42 doesn't wrap a `0x00`-prefixed byte string
+    LinkPrefix,
+    /// Tag 42 wraps bytes which aren't the binary form of a CID
+    InvalidLink(CidError),
+    /// An item is valid CBOR but not in the canonical form strict decoding requires
+    NonCanonical(&'static str),
+    /// A map contains the same key more than once
+    DuplicateKey(String),
+    /// A float is NaN or infinite, which the Data Model doesn't admit
+    NonFiniteFloat(f64),
+    /// Lists, maps and tags are nested more than [`MAX_NESTING`] deep
+    TooDeep,
+}
+
+impl fmt::Display for DecodeReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of input"),
+            Self::TrailingBytes => write!(f, "trailing bytes after the value"),
+            Self::Reserved(info) => write!(f, "additional information {} is reserved", info),
+            Self::IndefiniteArgument(major) => {
+                write!(f, "major type {} can't have an indefinite length", major)
+            }
+            Self::UnexpectedBreak => write!(f, "break outside of an indefinite-length item"),
+            Self::InvalidChunk => write!(f, "indefinite-length string has an invalid chunk"),
+            Self::UnsupportedSimple(value) => {
+                write!(f, "simple value {} isn't in the Data Model", value)
+            }
+            Self::IntOutOfRange(n) => write!(f, "integer {} is out of range", n),
+            Self::InvalidUtf8 => write!(f, "text string isn't valid UTF-8"),
+            Self::NonStringKey => write!(f, "map key isn't a text string"),
+            Self::UnsupportedTag(tag) => write!(f, "tag {} isn't supported", tag),
+            Self::LinkPrefix => write!(f, "tag 42 doesn't contain 0x00-prefixed bytes"),
+            Self::InvalidLink(err) => write!(f, "tag 42 doesn't contain a valid CID: {}", err),
+            Self::NonCanonical(what) => write!(f, "non-canonical {}", what),
+            Self::DuplicateKey(key) => write!(f, "duplicate map key {:?}", key),
+            Self::NonFiniteFloat(x) => write!(f, "float {} isn't in the Data Model", x),
+            Self::TooDeep => write!(f, "items are nested more than {} deep", MAX_NESTING),
+        }
+    }
+}
+
+/// Why a value couldn't be encoded as DAG-CBOR
+#[derive(Clone, Debug, PartialEq)]
+pub enum EncodeError {
+    /// A float is NaN or infinite, which DAG-CBOR forbids
+    NonFiniteFloat(f64),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::NonFiniteFloat(x) => write!(f, "float {} can't be expressed in DAG-CBOR", x),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+/// Decodes a value from DAG-CBOR, tolerating valid CBOR which isn't in canonical form
+///
+/// Duplicate map keys are still rejected since it's ambiguous which entry is meant.
+///
+/// # Errors
+///
+/// Fails if `bytes` aren't a single CBOR item or if the item doesn't describe a Data Model value, such as a NaN or
+/// infinite float.
+pub fn decode(bytes: &[u8]) -> Result<Value, DecodeError> {
+    Decoder::new(bytes, false).decode()
+}
+
+/// Decodes a value from DAG-CBOR which must be in canonical form
+///
+/// # Errors
+///
+/// Fails for the same reasons as [`decode`], and also if the input uses indefinite lengths, floats shorter than 64
+/// bits, unsorted map keys, or heads longer than necessary.
+pub fn decode_strict(bytes: &[u8]) -> Result<Value, DecodeError> {
+    Decoder::new(bytes, true).decode()
+}
+
+/// Encodes a value as canonical DAG-CBOR
+///
+/// # Errors
+///
+/// Fails if the value contains a NaN or infinite float.
+pub fn encode(value: &Value) -> Result<Vec<u8>, EncodeError> {
+    let mut out = vec![];
+    encode_into(value, &mut out)?;
+    Ok(out)
+}
+
+fn encode_into(value: &Value, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+    match value {
+        Value::Null => out.push(0xf6),
+        Value::Bool(false) => out.push(0xf4),
+        Value::Bool(true) => out.push(0xf5),
+        Value::Int(i) if *i >= 0 => write_head(out, 0, i.unsigned_abs()),
+        Value::Int(i) => write_head(out, 1, i.unsigned_abs() - 1),
+        Value::Float(x) if !x.is_finite() => return Err(EncodeError::NonFiniteFloat(*x)),
+        Value::Float(x) => {
+            out.push(0xfb);
+            out.extend_from_slice(&x.to_bits().to_be_bytes());
+        }
+        Value::String(s) => {
+            write_head(out, 3, length(s.len()));
+            out.extend_from_slice(s.as_bytes());
+        }
+        Value::Bytes(b) => {
+            write_head(out, 2, length(b.len()));
+            out.extend_from_slice(b);
+        }
+        Value::List(items) => {
+            write_head(out, 4, length(items.len()));
+            for item in items {
+                encode_into(item, out)?;
+            }
+        }
+        Value::Map(entries) => {
+            let mut entries: Vec<_> = entries.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| key_order(a, b));
+            write_head(out, 5, length(entries.len()));
+            for (key, value) in entries {
+                write_head(out, 3, length(key.len()));
+                out.extend_from_slice(key.as_bytes());
+                encode_into(value, out)?;
+            }
+        }
+        Value::Link(cid) => {
+            let binary = cid.to_bytes();
+            write_head(out, 6, LINK_TAG);
+            write_head(out, 2, length(binary.len() + 1));
+            out.push(0x00);
+            out.extend_from_slice(&binary);
+        }
+    }
+    Ok(())
+}
+
+/// Writes the shortest head for the major type and argument
+fn write_head(out: &mut Vec<u8>, major: u8, argument: u64) {
+    let bytes = argument.to_be_bytes();
+    let (info, len) = match argument {
+        0..=23 => (bytes[7], 0),
+        24..=0xff => (24, 1),
+        0x100..=0xffff => (25, 2),
+        0x1_0000..=0xffff_ffff => (26, 4),
+        _ => (27, 8),
+    };
+    out.push(major << 5 | info);
+    out.extend_from_slice(&bytes[8 - len..]);
+}
+
+fn length(len: usize) -> u64 {
+    u64::try_from(len).expect("lengths fit in 64 bits")
+}
+
+/// Canonical DAG-CBOR key order: shorter keys first, then bytewise
+fn key_order(a: &str, b: &str) -> std::cmp::Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Widens an IEEE 754 half-precision float
+fn half_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f64::from(bits & 0x3ff);
+    sign * match exponent {
+        0 => mantissa * 2_f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2_f64.powi(exponent - 15),
+    }
+}
+
+/// Head of a CBOR item: its major type, additional information, and the argument that information describes
+struct Head {
+    major: u8,
+    info: u8,
+    argument: u64,
+}
+
+impl Head {
+    const fn is_break(&self) -> bool {
+        self.major == 7 && self.info == 31
+    }
+
+    const fn is_indefinite(&self) -> bool {
+        self.info == 31
+    }
+}
+
+const fn error_at(offset: usize, reason: DecodeReason) -> DecodeError {
+    DecodeError { offset, reason }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    strict: bool,
+    /// Lists, maps and tags the current item is within
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    const fn new(bytes: &'a [u8], strict: bool) -> Self {
+        Self {
+            bytes,
+            offset: 0,
+            strict,
+            depth: 0,
+        }
+    }
+
+    fn decode(mut self) -> Result<Value, DecodeError> {
+        let value = self.value()?;
+        if self.offset < self.bytes.len() {
+            return Err(error_at(self.offset, DecodeReason::TrailingBytes));
+        }
+        Ok(value)
+    }
+
+    fn non_canonical(&self, start: usize, what: &'static str) -> Result<(), DecodeError> {
+        if self.strict {
+            Err(error_at(start, DecodeReason::NonCanonical(what)))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| error_at(self.bytes.len(), DecodeReason::UnexpectedEnd))?;
+        let taken = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(taken)
+    }
+
+    fn head(&mut self) -> Result<Head, DecodeError> {
+        let start = self.offset;
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        let argument = match info {
+            0..=23 => u64::from(info),
+            24..=27 => {
+                let len = 1 << (info - 24);
+                let mut buffer = [0; 8];
+                buffer[8 - len..].copy_from_slice(self.take(len)?);
+                u64::from_be_bytes(buffer)
+            }
+            28..=30 => return Err(error_at(start, DecodeReason::Reserved(info))),
+            _ if matches!(major, 0 | 1 | 6) => {
+                return Err(error_at(start, DecodeReason::IndefiniteArgument(major)))
+            }
+            _ => 0,
+        };
+        // floats and simple values are checked separately, as their heads aren't integer arguments
+        if major != 7 {
+            let minimal = match info {
+                24 => argument >= 24,
+                25 => argument > 0xff,
+                26 => argument > 0xffff,
+                27 => argument > 0xffff_ffff,
+                _ => true,
+            };
+            if !minimal {
+                self.non_canonical(start, "integer head")?;
+            }
+        }
+        Ok(Head {
+            major,
+            info,
+            argument,
+        })
+    }
+
+    fn value(&mut self) -> Result<Value, DecodeError> {
+        let start = self.offset;
+        let head = self.head()?;
+        if head.is_indefinite() && !head.is_break() && (2..=5).contains(&head.major) {
+            self.non_canonical(start, "indefinite length")?;
+        }
+        Ok(match head.major {
+            0 => Value::Int(Self::int(start, i128::from(head.argument))?),
+            1 => Value::Int(Self::int(start, -1 - i128::from(head.argument))?),
+            2 => Value::Bytes(self.string(start, &head)?),
+            3 => Value::String(self.text(start, &head)?),
+            4 => Value::List(self.nested(start, |this| this.list(&head))?),
+            5 => Value::Map(self.nested(start, |this| this.map(&head))?),
+            6 => self.nested(start, |this| this.tagged(start, head.argument))?,
+            _ => self.simple(start, &head)?,
+        })
+    }
+
+    /// Decodes the contents of a list, map or tag, failing if they'd be nested too deep
+    fn nested<T>(
+        &mut self,
+        start: usize,
+        decode: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError> {
+        if self.depth == MAX_NESTING {
+            return Err(error_at(start, DecodeReason::TooDeep));
+        }
+        self.depth += 1;
+        let decoded = decode(self);
+        self.depth -= 1;
+        decoded
+    }
+
+    fn int(start: usize, n: i128) -> Result<i64, DecodeError> {
+        i64::try_from(n).map_err(|_| error_at(start, DecodeReason::IntOutOfRange(n)))
+    }
+
+    fn definite_len(start: usize, argument: u64) -> Result<usize, DecodeError> {
+        usize::try_from(argument).map_err(|_| error_at(start, DecodeReason::UnexpectedEnd))
+    }
+
+    /// Contents of a byte or text string, concatenating the chunks of an indefinite-length string
+    fn string(&mut self, start: usize, head: &Head) -> Result<Vec<u8>, DecodeError> {
+        if !head.is_indefinite() {
+            let len = Self::definite_len(start, head.argument)?;
+            return Ok(self.take(len)?.to_vec());
+        }
+        let mut contents = vec![];
+        loop {
+            let chunk_start = self.offset;
+            let chunk = self.head()?;
+            if chunk.is_break() {
+                return Ok(contents);
+            }
+            if chunk.major != head.major || chunk.is_indefinite() {
+                return Err(error_at(chunk_start, DecodeReason::InvalidChunk));
+            }
+            let len = Self::definite_len(chunk_start, chunk.argument)?;
+            contents.extend_from_slice(self.take(len)?);
+        }
+    }
+
+    fn text(&mut self, start: usize, head: &Head) -> Result<String, DecodeError> {
+        String::from_utf8(self.string(start, head)?)
+            .map_err(|_| error_at(start, DecodeReason::InvalidUtf8))
+    }
+
+    /// Whether another item of a container follows, consuming the break ending an indefinite-length one
+    fn has_next(&mut self, head: &Head, decoded: usize) -> bool {
+        if head.is_indefinite() {
+            if self.bytes.get(self.offset) == Some(&0xff) {
+                self.offset += 1;
+                false
+            } else {
+                true
+            }
+        } else {
+            u64::try_from(decoded).is_ok_and(|decoded| decoded < head.argument)
+        }
+    }
+
+    fn list(&mut self, head: &Head) -> Result<Vec<Value>, DecodeError> {
+        let mut items = vec![];
+        while self.has_next(head, items.len()) {
+            items.push(self.value()?);
+        }
+        Ok(items)
+    }
+
+    fn map(&mut self, head: &Head) -> Result<BTreeMap<String, Value>, DecodeError> {
+        let mut entries = BTreeMap::new();
+        let mut previous: Option<String> = None;
+        while self.has_next(head, entries.len()) {
+            let key_start = self.offset;
+            let key = self.key()?;
+            if entries.contains_key(&key) {
+                return Err(error_at(key_start, DecodeReason::DuplicateKey(key)));
+            }
+            if let Some(previous) = &previous {
+                if key_order(previous, &key) == std::cmp::Ordering::Greater {
+                    self.non_canonical(key_start, "map key order")?;
+                }
+            }
+            let value = self.value()?;
+            entries.insert(key.clone(), value);
+            previous = Some(key);
+        }
+        Ok(entries)
+    }
+
+    fn key(&mut self) -> Result<String, DecodeError> {
+        let start = self.offset;
+        match self.value()? {
+            Value::String(key) => Ok(key),
+            _ => Err(error_at(start, DecodeReason::NonStringKey)),
+        }
+    }
+
+    fn tagged(&mut self, start: usize, tag: u64) -> Result<Value, DecodeError> {
+        if tag != LINK_TAG {
+            return Err(error_at(start, DecodeReason::UnsupportedTag(tag)));
+        }
+        if let Value::Bytes(bytes) = self.value()? {
+            if let Some((0x00, binary)) = bytes.split_first() {
+                return Cid::from_bytes(binary)
+                    .map(Value::Link)
+                    .map_err(|err| error_at(start, DecodeReason::InvalidLink(err)));
+            }
+        }
+        Err(error_at(start, DecodeReason::LinkPrefix))
+    }
+
+    fn simple(&mut self, start: usize, head: &Head) -> Result<Value, DecodeError> {
+        let float = match head.info {
+            20 => return Ok(Value::Bool(false)),
+            21 => return Ok(Value::Bool(true)),
+            22 => return Ok(Value::Null),
+            25 => half_to_f64(u16::try_from(head.argument).unwrap_or_default()),
+            26 => f64::from(f32::from_bits(
+                u32::try_from(head.argument).unwrap_or_default(),
+            )),
+            27 => f64::from_bits(head.argument),
+            31 => return Err(error_at(start, DecodeReason::UnexpectedBreak)),
+            24 => {
+                let value = head.argument.to_be_bytes()[7];
+                return Err(error_at(start, DecodeReason::UnsupportedSimple(value)));
+            }
+            info => return Err(error_at(start, DecodeReason::UnsupportedSimple(info))),
+        };
+        if head.info != 27 {
+            self.non_canonical(start, "float width")?;
+        }
+        if !float.is_finite() {
+            return Err(error_at(start, DecodeReason::NonFiniteFloat(float)));
+        }
+        Ok(Value::Float(float))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn reason(result: Result<Value, DecodeError>) -> DecodeReason {
+        result.unwrap_err().reason
+    }
+
+    #[test]
+    fn roundtrips_canonically() {
+        let value = crate::codec::dag_json::decode(
+            br#"{"bb": [1, -24, -25, 256, 1.5], "a": {"/": {"bytes": "AP8"}}, "c": null, "t": true,
+                 "v0": {"/": "QmbWqxBEKC3P8tqsKc98xmWNzrzDtRLMiMPL8wBuTGsMnR"},
+                 "v1": {"/": "bafyreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"}}"#,
+        )
+        .unwrap();
+
+        let encoded = encode(&value).unwrap();
+        assert_eq!(&encoded[..4], [0xa6, 0x61, b'a', 0x42]);
+        assert_eq!(decode_strict(&encoded).unwrap(), value);
+    }
+
+    #[test]
+    fn lenient_decoding_accepts_valid_non_canonical_cbor() {
+        // {_ "b": 1, "a": [_ 2]} with a two-byte head for 1 and a half-precision 1.5
+        let bytes = [
+            0xbf, 0x61, b'b', 0x18, 0x01, 0x61, b'a', 0x9f, 0x02, 0xf9, 0x3e, 0x00, 0xff, 0xff,
+        ];
+        let mut expected = BTreeMap::new();
+        expected.insert(
+            "a".to_string(),
+            Value::List(vec![Value::Int(2), Value::Float(1.5)]),
+        );
+        expected.insert("b".to_string(), Value::Int(1));
+
+        assert_eq!(decode(&bytes).unwrap(), Value::Map(expected));
+        assert_eq!(
+            decode_strict(&bytes).unwrap_err().to_string(),
+            "at byte 0: non-canonical indefinite length"
+        );
+    }
+
+    #[test]
+    fn strict_decoding_rejects_non_canonical_cbor() {
+        assert_eq!(
+            reason(decode_strict(&[0x18, 0x17])),
+            DecodeReason::NonCanonical("integer head")
+        );
+        assert_eq!(
+            reason(decode_strict(&[
+                0xa2, 0x62, b'a', b'a', 0x01, 0x61, b'b', 0x02
+            ])),
+            DecodeReason::NonCanonical("map key order")
+        );
+        assert_eq!(
+            reason(decode_strict(&[0xfa, 0x3f, 0xc0, 0x00, 0x00])),
+            DecodeReason::NonCanonical("float width")
+        );
+        assert_eq!(
+            reason(decode(&[0xa2, 0x61, b'a', 0x01, 0x61, b'a', 0x02])),
+            DecodeReason::DuplicateKey("a".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_what_cant_be_decoded_or_encoded() {
+        assert_eq!(reason(decode(&[0x82, 0x01])), DecodeReason::UnexpectedEnd);
+        assert_eq!(reason(decode(&[0x01, 0x02])), DecodeReason::TrailingBytes);
+        assert_eq!(reason(decode(&[0xf7])), DecodeReason::UnsupportedSimple(23));
+        assert!(matches!(
+            reason(decode(&[0xf9, 0x7e, 0x00])),
+            DecodeReason::NonFiniteFloat(x) if x.is_nan()
+        ));
+        assert!(matches!(
+            reason(decode(&[0xf9, 0xfc, 0x00])),
+            DecodeReason::NonFiniteFloat(x) if x == f64::NEG_INFINITY
+        ));
+        for &(byte, major) in &[(0x1f, 0), (0x3f, 1), (0xdf, 6)] {
+            assert_eq!(
+                reason(decode(&[byte, 0x00])),
+                DecodeReason::IndefiniteArgument(major)
+            );
+        }
+        let mut deep = vec![0x81; 1 << 20];
+        deep.push(0x00);
+        assert_eq!(
+            decode(&deep).unwrap_err(),
+            error_at(MAX_NESTING, DecodeReason::TooDeep)
+        );
+        deep.drain(..deep.len() - MAX_NESTING - 1);
+        assert!(decode(&deep).is_ok());
+        assert_eq!(
+            reason(decode(&[0xc1, 0x00])),
+            DecodeReason::UnsupportedTag(1)
+        );
+        assert_eq!(
+            reason(decode(&[0xd8, 0x2a, 0x41, 0x01])),
+            DecodeReason::LinkPrefix
+        );
+        assert_eq!(
+            reason(decode(&[0xd8, 0x2a, 0x42, 0x00, 0x02])),
+            DecodeReason::InvalidLink(CidError::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            reason(decode(&[0xa1, 0x01, 0x02])),
+            DecodeReason::NonStringKey
+        );
+        assert_eq!(
+            reason(decode(&[0x3b, 0x80, 0, 0, 0, 0, 0, 0, 0])),
+            DecodeReason::IntOutOfRange(-1 - (1_i128 << 63))
+        );
+        assert!(matches!(
+            encode(&Value::Float(f64::INFINITY)),
+            Err(EncodeError::NonFiniteFloat(_))
+        ));
+    }
+}
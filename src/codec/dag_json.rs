@@ -0,0 +1,97 @@
+//! [DAG-JSON](https://specs.ipld.io/block-layer/codecs/dag-json.html), a JSON encoding of Data Model values
+//!
+//! Links are encoded as `{"/": "<cid>"}` and bytes as `{"/": {"bytes": "<base64>"}}`, with unpadded standard base64.
+//! Numbers written with a decimal point or exponent are floats and all others are ints. Map keys are written sorted
+//! by their UTF-8 bytes, so equal values always encode identically.
+
+use std::{convert::TryFrom, fmt};
+
+use crate::{value::JsonError, Value};
+
+/// Why bytes couldn't be decoded as DAG-JSON
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The bytes aren't JSON
+    Syntax(serde_json::Error),
+    /// The JSON doesn't describe a Data Model value
+    Value(JsonError),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Syntax(err) => write!(f, "{}", err),
+            Self::Value(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Syntax(err) => Some(err),
+            Self::Value(err) => Some(err),
+        }
+    }
+}
+
+/// Decodes a value from DAG-JSON
+///
+/// # Errors
+///
+/// Fails if `bytes` aren't JSON, or if the JSON contains an integer outside the range of [`Value::Int`], bytes
+/// which aren't valid base64, or a link which isn't a valid CID.
+pub fn decode(bytes: &[u8]) -> Result<Value, DecodeError> {
+    let json: serde_json::Value = serde_json::from_slice(bytes).map_err(DecodeError::Syntax)?;
+    Value::try_from(json).map_err(DecodeError::Value)
+}
+
+/// Encodes a value as DAG-JSON, without insignificant whitespace
+///
+/// # Errors
+///
+/// Fails if the value contains a NaN or infinite float, which JSON can't express.
+pub fn encode(value: &Value) -> Result<Vec<u8>, JsonError> {
+    Ok(serde_json::Value::try_from(value)?.to_string().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn roundtrip(dag_json: &str) -> String {
+        String::from_utf8(encode(&decode(dag_json.as_bytes()).unwrap()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn roundtrips_with_sorted_keys() {
+        assert_eq!(
+            roundtrip(
+                r#"{"z": [1, 1.0, -2.5e3], "a": {"/": {"bytes": "AP8"}}, "b": {"/": "bafyreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"}, "n": null}"#
+            ),
+            r#"{"a":{"/":{"bytes":"AP8"}},"b":{"/":"bafyreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"},"n":null,"z":[1,1.0,-2500.0]}"#
+        );
+    }
+
+    #[test]
+    fn distinguishes_ints_from_floats() {
+        assert_eq!(decode(b"1").unwrap(), Value::Int(1));
+        assert_eq!(decode(b"1.0").unwrap(), Value::Float(1.0));
+        assert_eq!(decode(b"1e2").unwrap(), Value::Float(100.0));
+    }
+
+    #[test]
+    fn reports_what_cant_be_decoded_or_encoded() {
+        assert!(matches!(decode(b"{"), Err(DecodeError::Syntax(_))));
+        assert!(matches!(
+            decode(br#"{"/": {"bytes": "*"}}"#),
+            Err(DecodeError::Value(JsonError::InvalidBytes(_)))
+        ));
+        assert!(matches!(
+            encode(&Value::List(vec![Value::Float(f64::INFINITY)])),
+            Err(JsonError::NonFiniteFloat(_))
+        ));
+    }
+}
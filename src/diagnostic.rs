@@ -0,0 +1,233 @@
+use std::{fmt::Write, path::Path};
+
+use peg::{error::ParseError, str::LineCol};
+
+const BOLD_RED: &str = "\x1b[1;31m";
+const BOLD_BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Renders `label: message` with `label` highlighted in red
+pub(crate) fn render_message(label: &str, message: &str, color: bool) -> String {
+    let style = Style { color };
+    format!(
+        "{}{}\n",
+        style.paint(BOLD_RED, label),
+        style.paint(BOLD, &format!(": {}", message))
+    )
+}
+
+/// Renders a DSL parse error in the style of `rustc`, pointing at the offending line of `text`
+pub(crate) fn render_parse_error(
+    path: &Path,
+    text: &str,
+    err: &ParseError<LineCol>,
+    color: bool,
+) -> String {
+    let style = Style { color };
+    let LineCol { line, column, .. } = err.location;
+    let source_line = text.lines().nth(line - 1).unwrap_or("");
+    let gutter = " ".repeat(line.to_string().len());
+
+    // mirror tabs in the padding so the caret lines up however wide the terminal renders them
+    let padding: String = source_line
+        .get(..column - 1)
+        .unwrap_or(source_line)
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    let mut out = render_message("error", &summarize(text, err), color);
+    let _ = writeln!(
+        out,
+        "{}{} {}:{}:{}",
+        gutter,
+        style.paint(BOLD_BLUE, "-->"),
+        path.display(),
+        line,
+        column
+    );
+    let _ = writeln!(out, "{} {}", gutter, style.paint(BOLD_BLUE, "|"));
+    let _ = writeln!(
+        out,
+        "{} {}",
+        style.paint(BOLD_BLUE, &format!("{} |", line)),
+        source_line
+    );
+    let _ = writeln!(
+        out,
+        "{} {} {}{}",
+        gutter,
+        style.paint(BOLD_BLUE, "|"),
+        padding,
+        style.paint(BOLD_RED, "^")
+    );
+    out
+}
+
+/// Summarizes what the parser expected to find, e.g. "expected `representation` or end of line after `}`"
+pub(crate) fn summarize(text: &str, err: &ParseError<LineCol>) -> String {
+    let mut expected: Vec<(u8, String)> = err.expected.tokens().map(describe_token).collect();
+    expected.sort();
+    expected.dedup();
+
+    // optional whitespace is nearly always acceptable so it's only worth mentioning on its own
+    if expected.len() > 1 {
+        expected.retain(|(rank, _)| *rank != WHITESPACE_RANK);
+    }
+
+    // likewise, a name can always go on a little longer
+    let previous = previous_token(text, err.location.offset);
+    let continues_name = previous
+        .is_some_and(|p| text[..err.location.offset].ends_with(p) && p.chars().all(is_name_char));
+    if continues_name && expected.iter().any(|(rank, _)| *rank != NAME_CHAR_RANK) {
+        expected.retain(|(rank, _)| *rank != NAME_CHAR_RANK);
+    }
+
+    let mut summary = format!(
+        "expected {}",
+        join_alternatives(&expected.into_iter().map(|(_, t)| t).collect::<Vec<_>>())
+    );
+    if let Some(previous) = previous {
+        let _ = write!(summary, " after `{}`", previous);
+    }
+    summary
+}
+
+const NAME_CHAR_RANK: u8 = 1;
+const WHITESPACE_RANK: u8 = 2;
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Describes a token reported by the parser, paired with a rank used to order the descriptions
+fn describe_token(token: &'static str) -> (u8, String) {
+    if token == "EOF" {
+        return (4, "end of file".to_string());
+    }
+
+    if token.len() >= 2 && token.starts_with('"') && token.ends_with('"') {
+        return match &token[1..token.len() - 1] {
+            "\\n" | "\\r\\n" => (3, "end of line".to_string()),
+            " " | "\\t" => (WHITESPACE_RANK, "whitespace".to_string()),
+            "\\\"" => (0, "`\"`".to_string()),
+            "_" => (NAME_CHAR_RANK, "`_`".to_string()),
+            literal => (0, format!("`{}`", literal)),
+        };
+    }
+
+    let description = match token {
+        "'A'..='Z'" => "an uppercase letter",
+        "'a'..='z'" => "a lowercase letter",
+        "'0'..='9'" => "a digit",
+        "[_]" => "any character",
+        _ => return (NAME_CHAR_RANK, format!("`{}`", token)),
+    };
+    (NAME_CHAR_RANK, description.to_string())
+}
+
+fn join_alternatives(alternatives: &[String]) -> String {
+    match alternatives {
+        [] => "something else".to_string(),
+        [only] => only.clone(),
+        [init @ .., last] => format!("{} or {}", init.join(", "), last),
+    }
+}
+
+/// Finds the token immediately preceding `offset` on the same line, skipping whitespace
+fn previous_token(text: &str, offset: usize) -> Option<&str> {
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let before = text[line_start..offset].trim_end();
+    let last = before.chars().last()?;
+
+    if is_name_char(last) {
+        let start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_name_char(*c))
+            .last()
+            .map_or(0, |(i, _)| i);
+        Some(&before[start..])
+    } else {
+        Some(&before[before.len() - last.len_utf8()..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::schema::schema_dsl;
+
+    use pretty_assertions::assert_eq;
+
+    fn render(text: &str, color: bool) -> String {
+        render_parse_error(
+            Path::new("example.ipldsch"),
+            text,
+            &schema_dsl::parse(text).unwrap_err(),
+            color,
+        )
+    }
+
+    #[test]
+    fn points_at_unexpected_token_after_struct() {
+        assert_eq!(
+            render("type Foo struct {\n  a Int\n}tuple\n", false),
+            "error: expected `representation` or end of line after `}`
+ --> example.ipldsch:3:2
+  |
+3 | }tuple
+  |  ^
+"
+        );
+    }
+
+    #[test]
+    fn describes_character_classes() {
+        assert_eq!(
+            render("type foo int\n", false),
+            "error: expected an uppercase letter after `type`
+ --> example.ipldsch:1:6
+  |
+1 | type foo int
+  |      ^
+"
+        );
+    }
+
+    #[test]
+    fn omits_characters_continuing_a_name() {
+        assert_eq!(
+            render("type Foo [Bar\n", false),
+            "error: expected `]` after `Bar`
+ --> example.ipldsch:1:14
+  |
+1 | type Foo [Bar
+  |              ^
+"
+        );
+    }
+
+    #[test]
+    fn colors_only_when_requested() {
+        let text = "type Foo nope\n";
+        assert!(!render(text, false).contains('\x1b'));
+        assert!(render(text, true).contains(BOLD_RED));
+    }
+}
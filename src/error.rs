@@ -0,0 +1,180 @@
+use std::{fmt, io, path::PathBuf};
+
+use peg::{error::ParseError, str::LineCol};
+
+use crate::{
+    diagnostic,
+    schema::{Problem, TypeName},
+    validation::Mismatch,
+};
+
+/// Everything that can go wrong while validating or generating schemas and data
+#[derive(Debug)]
+pub enum Error {
+    /// Command-line options contradict each other, e.g. a minimum is above its maximum
+    Usage(String),
+    /// Reading an input file or writing output failed
+    Io {
+        /// File being read, if the failure wasn't while writing output
+        path: Option<PathBuf>,
+        /// What went wrong
+        source: io::Error,
+    },
+    /// A schema file isn't syntactically valid DSL
+    Parse {
+        /// The schema file
+        path: PathBuf,
+        /// Contents of the schema file, kept to render diagnostics
+        text: String,
+        /// Where parsing stopped and which tokens were expected there
+        source: ParseError<LineCol>,
+    },
+    /// A schema parsed but breaks one or more of the rules for well-formed schemas
+    Schema {
+        /// The schema file
+        path: PathBuf,
+        /// Every rule the schema breaks
+        problems: Vec<Problem>,
+    },
+    /// A data file couldn't be decoded into a value
+    Decode {
+        /// The data file or CAR archive
+        path: PathBuf,
+        /// Why it couldn't be decoded
+        message: String,
+    },
+    /// Data doesn't conform to the schema it was checked against
+    Data {
+        /// The data file or CAR archive
+        path: PathBuf,
+        /// Every place the data doesn't conform
+        problems: Vec<Mismatch>,
+    },
+    /// The type given for data isn't in the schema, or no type was given and the schema doesn't have exactly one type
+    /// which no other type refers to
+    RootType {
+        /// The schema file
+        path: PathBuf,
+        /// Type passed with `--type`, if any
+        given: Option<String>,
+        /// Types which could be given instead
+        candidates: Vec<TypeName>,
+    },
+}
+
+impl Error {
+    /// Process exit code for the CLI, distinct for each category of error
+    ///
+    /// | code | category |
+    /// |------|----------|
+    /// | 1    | usage    |
+    /// | 2    | I/O      |
+    /// | 3    | schema syntax |
+    /// | 4    | schema semantics |
+    /// | 5    | data     |
+    /// | 6    | type of the data |
+    #[must_use]
+    pub const fn exit_code(&self) -> i32 {
+        match self {
+            Self::Usage(_) => 1,
+            Self::Io { .. } => 2,
+            Self::Parse { .. } => 3,
+            Self::Schema { .. } => 4,
+            Self::Decode { .. } | Self::Data { .. } => 5,
+            Self::RootType { .. } => 6,
+        }
+    }
+
+    /// Renders a human-readable report of the error, optionally using ANSI colors
+    ///
+    /// Parse errors point at the offending line of the schema file.
+    #[must_use]
+    pub fn render(&self, color: bool) -> String {
+        match self {
+            Self::Parse { path, text, source } => {
+                diagnostic::render_parse_error(path, text, source, color)
+            }
+            _ => diagnostic::render_message("error", &self.to_string(), color),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Self {
+        Self::Io { path: None, source }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Usage(message) => write!(f, "{}", message),
+            Self::Io {
+                path: Some(path),
+                source,
+            } => write!(f, "failed to read {}: {}", path.display(), source),
+            Self::Io { path: None, source } => write!(f, "failed to write output: {}", source),
+            Self::Parse { path, text, source } => write!(
+                f,
+                "failed to parse {}:{}: {}",
+                path.display(),
+                source.location,
+                diagnostic::summarize(text, source)
+            ),
+            Self::Schema { path, problems } => {
+                write!(f, "invalid schema {}", path.display())?;
+                write_problems(f, problems)
+            }
+            Self::Decode { path, message } => {
+                write!(f, "failed to decode {}: {}", path.display(), message)
+            }
+            Self::Data { path, problems } => {
+                write!(f, "invalid data {}", path.display())?;
+                write_problems(f, problems)
+            }
+            Self::RootType {
+                path,
+                given,
+                candidates,
+            } => {
+                match given {
+                    Some(name) => write!(f, "type `{}` isn't defined in {}", name, path.display())?,
+                    None => write!(f, "can't tell which type in {} to use", path.display())?,
+                }
+                if candidates.is_empty() {
+                    write!(f, "; pass --type with a prelude type such as `Any`")
+                } else {
+                    let candidates: Vec<String> = candidates
+                        .iter()
+                        .map(|name| format!("`{}`", name))
+                        .collect();
+                    write!(f, "; pass --type with one of {}", candidates.join(", "))
+                }
+            }
+        }
+    }
+}
+
+fn write_problems<P: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    problems: &[P],
+) -> Result<(), fmt::Error> {
+    for problem in problems {
+        write!(f, "\n  - {}", problem)?;
+    }
+    Ok(())
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { source, .. } => Some(source),
+            Self::Usage(_)
+            | Self::Schema { .. }
+            | Self::Decode { .. }
+            | Self::Data { .. }
+            | Self::RootType { .. } => None,
+        }
+    }
+}
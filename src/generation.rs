@@ -0,0 +1,663 @@
+//! Generating arbitrary [`Value`]s of the types described by a [`Schema`]
+//!
+//! Values are generated in the form their types are represented as in the Data Model, so they pass
+//! [`Schema::validate`]. Their size is bounded by [`Limits`]: beyond its nesting depth or node budget, values are kept as
+//! small as their types allow, which keeps them finite even for recursive types.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+};
+
+use proptest::{
+    collection::vec,
+    prelude::*,
+    strategy::{Just, NewTree, ValueTree},
+    test_runner::TestRunner,
+};
+
+use crate::{
+    cid,
+    schema::{
+        enum_representation, struct_representation, union_representation, EnumRepresentation,
+        InlineDefn, MapRepresentation, RepresentationKind, Schema, StructRepresentation, Type,
+        TypeEnum, TypeList, TypeMap, TypeName, TypeStruct, TypeTerm, TypeUnion,
+        UnionRepresentation, PRELUDE,
+    },
+    Cid, Value,
+};
+
+/// Bounds on the size of generated values
+///
+/// Once a limit is reached, collections are empty, optional fields are omitted, nullable values are null and union
+/// members are chosen so that recursion ends. Values may still exceed the limits by what their types require, e.g. a
+/// struct's required fields. A range whose end is before its start is treated as its start alone.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Limits {
+    /// Levels of nested collections, structs and unions after which values are kept as small as their types allow
+    pub max_depth: usize,
+    /// Number of entries in each map or list
+    ///
+    /// Maps may have fewer entries than the minimum, since entries whose keys collide with earlier ones are dropped,
+    /// as are those whose keys can't be represented, e.g. because they contain a `stringpairs` delimiter.
+    pub collection_size: RangeInclusive<usize>,
+    /// Number of characters in each string and of bytes in each bytes value
+    pub string_length: RangeInclusive<usize>,
+    /// Number of values, including those nested in others, after which values are kept as small as their types allow
+    pub max_nodes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: 4,
+            collection_size: 0..=10,
+            string_length: 0..=32,
+            max_nodes: 1000,
+        }
+    }
+}
+
+/// Strategy for values of a schema's type, created with [`Schema::values_of`]
+///
+/// Generated values don't shrink.
+#[derive(Clone, Debug)]
+pub struct ValueStrategy<'a> {
+    schema: &'a Schema,
+    root: String,
+    limits: Limits,
+}
+
+impl Schema {
+    /// Strategy generating values of the type named `root` within the default [`Limits`], in the form that type is
+    /// represented as
+    ///
+    /// Values of schemas failing [`Schema::check`] may not be valid, e.g. `null` is generated for undefined types.
+    #[must_use]
+    pub fn values_of(&self, root: &str) -> ValueStrategy<'_> {
+        self.values_within(root, Limits::default())
+    }
+
+    /// Strategy generating values of the type named `root` within `limits`
+    #[must_use]
+    pub fn values_within(&self, root: &str, limits: Limits) -> ValueStrategy<'_> {
+        ValueStrategy {
+            schema: self,
+            root: root.to_string(),
+            limits,
+        }
+    }
+}
+
+impl Strategy for ValueStrategy<'_> {
+    type Tree = Just<Value>;
+    type Value = Value;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        Ok(Just(
+            Generator::new(self.schema, &self.limits, runner).named(&self.root),
+        ))
+    }
+}
+
+struct Generator<'a> {
+    schema: &'a Schema,
+    runner: &'a mut TestRunner,
+    limits: &'a Limits,
+    /// Position of each inhabited declared type in an order where finite values of each can be built from values of
+    /// earlier types alone
+    order: BTreeMap<TypeName, usize>,
+    /// Levels of nesting left before values are kept as small as possible
+    depth: usize,
+    /// Values generated so far
+    nodes: usize,
+    /// Types being generated after a limit was reached, which bounds recursion even where no finite value is found
+    past_limit: usize,
+    /// Characters strings can't contain, or `None` unless the value is part of a string representation
+    reserved: Option<BTreeSet<char>>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(schema: &'a Schema, limits: &'a Limits, runner: &'a mut TestRunner) -> Self {
+        Self {
+            schema,
+            runner,
+            limits,
+            order: schema
+                .inhabitation_order()
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| (name, i))
+                .collect(),
+            depth: limits.max_depth,
+            nodes: 0,
+            past_limit: 0,
+            reserved: None,
+        }
+    }
+
+    fn draw<S: Strategy>(&mut self, strategy: S) -> S::Value {
+        strategy
+            .new_tree(self.runner)
+            .expect("strategy never rejects")
+            .current()
+    }
+
+    const fn at_limit(&self) -> bool {
+        self.depth == 0 || self.nodes >= self.limits.max_nodes
+    }
+
+    /// Generates a child of the current value
+    fn nested<T>(&mut self, generate: impl FnOnce(&mut Self) -> T) -> T {
+        let depth = self.depth;
+        self.depth = depth.saturating_sub(1);
+        let value = generate(self);
+        self.depth = depth;
+        value
+    }
+
+    /// Decides whether to leave out something optional, which is always done at the limit
+    fn omit(&mut self) -> bool {
+        self.at_limit() || self.draw(any::<bool>())
+    }
+
+    fn len(&mut self) -> usize {
+        if self.at_limit() {
+            0
+        } else {
+            self.draw(bounded(&self.limits.collection_size))
+        }
+    }
+
+    /// Chooses a union member, at the limit one whose values are built from the fewest other types
+    fn choose<'t, D>(&mut self, mut members: Vec<(D, &'t TypeName)>) -> Option<(D, &'t TypeName)> {
+        if members.is_empty() {
+            return None;
+        }
+        let index = if self.at_limit() {
+            (0..members.len()).min_by_key(|&i| self.position(members[i].1))?
+        } else {
+            self.draw(0..members.len())
+        };
+        Some(members.swap_remove(index))
+    }
+
+    /// Place of a type in the inhabitation order, where prelude types come first and uninhabited types last
+    fn position(&self, name: &TypeName) -> usize {
+        match self.schema.types().get(name.as_str()) {
+            Some(_) => self.order.get(name).map_or(usize::MAX, |i| i + 1),
+            None => 0,
+        }
+    }
+
+    fn term(&mut self, term: &TypeTerm, nullable: bool) -> Value {
+        if nullable
+            && self.reserved.is_none()
+            && (self.at_limit() || self.draw(proptest::bool::weighted(0.2)))
+        {
+            return Value::Null;
+        }
+        match term {
+            TypeTerm::TypeName(name) => self.named(name.as_str()),
+            TypeTerm::InlineDefn(inline) => match inline.as_ref() {
+                InlineDefn::Map(map) => self.map(map),
+                InlineDefn::List(list) => self.list(list),
+            },
+        }
+    }
+
+    fn named(&mut self, name: &str) -> Value {
+        let schema = self.schema;
+        match schema.resolve(name) {
+            // only schemas failing `Schema::check` have names which don't resolve
+            Err(_) => Value::Null,
+            // e.g. a tuple's optional field of its own type, which inhabitation doesn't account for
+            Ok(_) if self.past_limit > schema.types().len() + PRELUDE.len() => Value::Null,
+            Ok(ty) if self.at_limit() => {
+                self.past_limit += 1;
+                let value = self.ty(&ty);
+                self.past_limit -= 1;
+                value
+            }
+            Ok(ty) => self.ty(&ty),
+        }
+    }
+
+    fn ty(&mut self, ty: &Type) -> Value {
+        self.nodes += 1;
+        match ty {
+            Type::Bool(_) => Value::Bool(self.draw(any::<bool>())),
+            Type::String(_) => Value::String(self.string()),
+            Type::Bytes(_) => {
+                Value::Bytes(self.draw(vec(any::<u8>(), bounded(&self.limits.string_length))))
+            }
+            Type::Int(_) => Value::Int(self.draw(any::<i64>())),
+            Type::Float(_) => {
+                Value::Float(self.draw(proptest::num::f64::NORMAL | proptest::num::f64::ZERO))
+            }
+            Type::Link(_) => Value::Link(self.cid()),
+            Type::Map(map) => self.map(map),
+            Type::List(list) => self.list(list),
+            Type::Struct(s) => self.r#struct(s),
+            Type::Enum(e) => self.r#enum(e),
+            Type::Union(u) => self.union(u),
+            Type::Copy(copy) => self.named(copy.from_type().as_str()),
+        }
+    }
+
+    /// Generates a string without any reserved characters
+    fn string(&mut self) -> String {
+        let reserved = self.reserved.clone().unwrap_or_default();
+        let char = any::<char>().prop_filter("reserved character", move |c| !reserved.contains(c));
+        let chars = self.draw(vec(char, bounded(&self.limits.string_length)));
+        chars.into_iter().collect()
+    }
+
+    /// Generates a link to a block which isn't generated itself
+    fn cid(&mut self) -> Cid {
+        let codecs = [cid::DAG_CBOR, cid::DAG_JSON, cid::RAW];
+        let codec = codecs[self.draw(0..codecs.len())];
+        let block = self.draw(any::<[u8; 32]>());
+        Cid::new_v1(codec, &block)
+    }
+
+    /// Generates a value as a string without any characters of `delims`, or `None` if it isn't represented as a
+    /// string without them
+    fn string_form(
+        &mut self,
+        delims: &[&str],
+        generate: impl FnOnce(&mut Self) -> Value,
+    ) -> Option<String> {
+        let mut reserved = self.reserved.clone().unwrap_or_default();
+        reserved.extend(delims.iter().flat_map(|delim| delim.chars()));
+        let outer = self.reserved.replace(reserved.clone());
+        let value = generate(self);
+        self.reserved = outer;
+
+        match value {
+            Value::String(s) if !s.chars().any(|c| reserved.contains(&c)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn map(&mut self, map: &TypeMap) -> Value {
+        let len = self.len();
+        let key_type = map.key_type().as_str();
+        match map.representation() {
+            MapRepresentation::Map(_) => Value::Map(self.nested(|this| {
+                this.entries(len, |this| {
+                    let key = this.string_form(&[], |this| this.named(key_type))?;
+                    let value = this.term(map.value_type(), map.value_nullable());
+                    // DAG-JSON reads a map whose only key is "/" as a link or bytes
+                    (key != "/").then_some((key, value))
+                })
+            })),
+            MapRepresentation::StringPairs(pairs) => {
+                let delims = [pairs.inner_delim.as_str(), pairs.entry_delim.as_str()];
+                let entries: BTreeMap<String, String> = self.nested(|this| {
+                    this.entries(len, |this| {
+                        let key = this.string_form(&delims, |this| this.named(key_type))?;
+                        let value =
+                            this.string_form(&delims, |this| this.term(map.value_type(), false))?;
+                        Some((key, value))
+                    })
+                });
+                let text = join_pairs(&entries, &pairs.inner_delim, &pairs.entry_delim);
+                // delimiters overlapping each other can make entries decode differently
+                if split_pairs(&text, &pairs.inner_delim, &pairs.entry_delim) == Some(entries) {
+                    Value::String(text)
+                } else {
+                    Value::String(String::new())
+                }
+            }
+            MapRepresentation::ListPairs(_) => {
+                let entries: BTreeMap<String, Value> = self.nested(|this| {
+                    this.entries(len, |this| {
+                        let key = this.string_form(&[], |this| this.named(key_type))?;
+                        Some((key, this.term(map.value_type(), map.value_nullable())))
+                    })
+                });
+                Value::List(
+                    entries
+                        .into_iter()
+                        .map(|(key, value)| Value::List(vec![Value::String(key), value]))
+                        .collect(),
+                )
+            }
+            // advanced layouts aren't checked, so any value will do
+            MapRepresentation::Advanced(_) => Value::Map(BTreeMap::new()),
+        }
+    }
+
+    fn list(&mut self, list: &TypeList) -> Value {
+        let len = self.len();
+        Value::List(self.nested(|this| {
+            this.entries(len, |this| {
+                Some(this.term(list.value_type(), list.value_nullable()))
+            })
+        }))
+    }
+
+    /// Generates up to `len` entries of a collection, stopping early once the node budget runs out
+    fn entries<T, C: Default + Extend<T>>(
+        &mut self,
+        len: usize,
+        mut generate: impl FnMut(&mut Self) -> Option<T>,
+    ) -> C {
+        let mut entries = C::default();
+        for _ in 0..len {
+            if self.nodes >= self.limits.max_nodes {
+                break;
+            }
+            entries.extend(generate(self));
+        }
+        entries
+    }
+
+    /// Generates a struct in the form of its representation
+    ///
+    /// Fields of string representations whose values can't avoid the delimiters, e.g. enum members containing them,
+    /// are generated as empty strings, so such structs may not be valid.
+    fn r#struct(&mut self, s: &TypeStruct) -> Value {
+        self.nested(|this| match s.representation() {
+            StructRepresentation::Map(details) => this.struct_map(s, details),
+            StructRepresentation::Tuple(tuple) => {
+                let field_order = tuple
+                    .field_order
+                    .clone()
+                    .unwrap_or_else(|| s.fields().map(|(name, _)| name.clone()).collect());
+                Value::List(
+                    field_order
+                        .iter()
+                        .filter_map(|name| s.field(name.as_str()))
+                        .map(|field| this.term(field.type_term(), field.nullable()))
+                        .collect(),
+                )
+            }
+            StructRepresentation::StringPairs(pairs) => {
+                let delims = [pairs.inner_delim.as_str(), pairs.entry_delim.as_str()];
+                let mut entries = BTreeMap::new();
+                for (name, field) in s.fields() {
+                    if field.optional() && this.omit() {
+                        continue;
+                    }
+                    let value =
+                        this.string_form(&delims, |this| this.term(field.type_term(), false));
+                    match value {
+                        Some(value) => entries.insert(name.to_string(), value),
+                        None if field.optional() => continue,
+                        None => entries.insert(name.to_string(), String::new()),
+                    };
+                }
+                Value::String(join_pairs(&entries, &pairs.inner_delim, &pairs.entry_delim))
+            }
+            StructRepresentation::StringJoin(join) => {
+                let segments: Vec<String> = join
+                    .field_order
+                    .iter()
+                    .filter_map(|name| s.field(name.as_str()))
+                    .map(|field| {
+                        this.string_form(&[join.join.as_str()], |this| {
+                            this.term(field.type_term(), false)
+                        })
+                        .unwrap_or_default()
+                    })
+                    .collect();
+                Value::String(segments.join(&join.join))
+            }
+            StructRepresentation::ListPairs(_) => {
+                let mut pairs = vec![];
+                for (name, field) in s.fields() {
+                    if field.optional() && this.omit() {
+                        continue;
+                    }
+                    let value = this.term(field.type_term(), field.nullable());
+                    pairs.push(Value::List(vec![Value::String(name.to_string()), value]));
+                }
+                Value::List(pairs)
+            }
+        })
+    }
+
+    /// Generates the fields of a struct represented as a map, under their renamed keys
+    ///
+    /// Fields with implicit values are sometimes omitted, like optional fields.
+    fn struct_map(&mut self, s: &TypeStruct, details: &struct_representation::Map) -> Value {
+        let mut entries = BTreeMap::new();
+        for (name, field) in s.fields() {
+            let details = details.fields.get(name);
+            let implicit = details.is_some_and(|d| d.implicit.is_some());
+            if (field.optional() || implicit) && self.omit() {
+                continue;
+            }
+            let key = details
+                .and_then(|d| d.rename.clone())
+                .unwrap_or_else(|| name.to_string());
+            entries.insert(key, self.term(field.type_term(), field.nullable()));
+        }
+        Value::Map(entries)
+    }
+
+    fn r#enum(&mut self, e: &TypeEnum) -> Value {
+        match e.representation() {
+            EnumRepresentation::String(enum_representation::String(table)) => {
+                let reserved = self.reserved.clone().unwrap_or_default();
+                let strings: Vec<&str> = e
+                    .members()
+                    .map(|member| table.get(member).map_or(member.as_str(), String::as_str))
+                    .collect();
+                let fitting: Vec<&str> = strings
+                    .iter()
+                    .copied()
+                    .filter(|s| !s.chars().any(|c| reserved.contains(&c)))
+                    .collect();
+                let choices = if fitting.is_empty() { strings } else { fitting };
+                match choices.len() {
+                    0 => Value::Null,
+                    len => Value::String(choices[self.draw(0..len)].to_string()),
+                }
+            }
+            EnumRepresentation::Int(enum_representation::Int(table)) => {
+                let ints: Vec<i64> = e
+                    .members()
+                    .filter_map(|member| table.get(member).copied())
+                    .collect();
+                match ints.len() {
+                    0 => Value::Null,
+                    len => Value::Int(ints[self.draw(0..len)]),
+                }
+            }
+        }
+    }
+
+    fn union(&mut self, u: &TypeUnion) -> Value {
+        self.nested(|this| match u.representation() {
+            UnionRepresentation::Kinded(union_representation::Kinded(table)) => {
+                // only the string member can be part of a string representation
+                let members = table
+                    .iter()
+                    .filter(|(kind, _)| {
+                        this.reserved.is_none() || **kind == RepresentationKind::String
+                    })
+                    .map(|(_, member)| ((), member))
+                    .collect();
+                this.choose(members)
+                    .map_or(Value::Null, |((), member)| this.named(member.as_str()))
+            }
+            UnionRepresentation::Keyed(union_representation::Keyed(table)) => {
+                match this.choose(table.iter().collect()) {
+                    Some((key, member)) => {
+                        let content = this.named(member.as_str());
+                        Value::Map(std::iter::once((key.clone(), content)).collect())
+                    }
+                    None => Value::Null,
+                }
+            }
+            UnionRepresentation::Envelope(envelope) => {
+                match this.choose(envelope.discriminant_table.iter().collect()) {
+                    Some((discriminant, member)) => {
+                        let content = this.named(member.as_str());
+                        Value::Map(
+                            vec![
+                                (
+                                    envelope.discriminant_key.clone(),
+                                    Value::String(discriminant.clone()),
+                                ),
+                                (envelope.content_key.clone(), content),
+                            ]
+                            .into_iter()
+                            .collect(),
+                        )
+                    }
+                    None => Value::Null,
+                }
+            }
+            UnionRepresentation::Inline(inline) => {
+                match this.choose(inline.discriminant_table.iter().collect()) {
+                    Some((discriminant, member)) => match this.named(member.as_str()) {
+                        Value::Map(mut entries) => {
+                            entries.insert(
+                                inline.discriminant_key.clone(),
+                                Value::String(discriminant.clone()),
+                            );
+                            Value::Map(entries)
+                        }
+                        other => other,
+                    },
+                    None => Value::Null,
+                }
+            }
+            UnionRepresentation::BytePrefix(union_representation::BytePrefix {
+                discriminant_table,
+            }) => {
+                // only bytes can follow the prefix
+                let bytes: BTreeSet<RepresentationKind> =
+                    std::iter::once(RepresentationKind::Bytes).collect();
+                let members = discriminant_table
+                    .iter()
+                    .filter(|(member, _)| {
+                        this.schema.representation_kinds(member.as_str()).as_ref() == Some(&bytes)
+                    })
+                    .map(|(member, byte)| (*byte, member))
+                    .collect();
+                match this.choose(members) {
+                    Some((byte, member)) => match this.named(member.as_str()) {
+                        Value::Bytes(rest) => {
+                            Value::Bytes(std::iter::once(byte).chain(rest).collect())
+                        }
+                        other => other,
+                    },
+                    None => Value::Null,
+                }
+            }
+        })
+    }
+}
+
+/// `range`, or its start alone if it's empty
+fn bounded(range: &RangeInclusive<usize>) -> RangeInclusive<usize> {
+    *range.start()..=*range.end().max(range.start())
+}
+
+/// Joins entries into a string, each a key and value joined by `inner_delim`
+fn join_pairs(entries: &BTreeMap<String, String>, inner_delim: &str, entry_delim: &str) -> String {
+    entries
+        .iter()
+        .map(|(key, value)| format!("{}{}{}", key, inner_delim, value))
+        .collect::<Vec<_>>()
+        .join(entry_delim)
+}
+
+/// Splits a string into entries the way validation does, or `None` if an entry isn't a pair
+fn split_pairs(
+    text: &str,
+    inner_delim: &str,
+    entry_delim: &str,
+) -> Option<BTreeMap<String, String>> {
+    text.split(entry_delim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (key, value) = entry.split_once(inner_delim)?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::test_runner::{Config, RngAlgorithm, TestRng};
+
+    const SCHEMA: &str = "type Tree struct {\n  label Label\n  children [Tree]\n  parent optional nullable TreeLink\n  size Int (implicit 0)\n}\n\ntype TreeLink &Tree\n\ntype Label union {\n  | Name string\n  | Edge map\n  | Coded bytes\n} representation kinded\n\ntype Name string\n\ntype Pair struct {\n  key String\n  value Level\n} representation stringjoin {\n  join \":\"\n}\n\ntype Level enum {\n  | low\n  | high\n}\n\ntype Coded union {\n  | Bytes 1\n  | Tagged 2\n} representation byteprefix\n\ntype Tagged bytes\n\ntype Attrs {String:Level} representation stringpairs {\n  innerDelim \"=\"\n  entryDelim \"&\"\n}\n\ntype Point struct {\n  x Int\n  y optional Float\n} representation tuple\n\ntype Shape union {\n  | Point \"point\"\n  | Attrs \"attrs\"\n  | Pair \"pair\"\n} representation keyed\n\ntype Node union {\n  | Tree \"tree\"\n  | Edge \"edge\"\n} representation inline {\n  discriminantKey \"kind\"\n}\n\ntype Edge struct {\n  from Int\n  to Int\n  shape Shape\n}\n\ntype Step union {\n  | Node \"node\"\n  | Step \"step\"\n} representation envelope {\n  discriminantKey \"tag\"\n  contentKey \"content\"\n}\n";
+
+    #[test]
+    fn generated_values_are_valid_and_finite() {
+        let schema = Schema::from_dsl(SCHEMA).unwrap();
+        schema.check().unwrap();
+        let mut runner = TestRunner::new_with_rng(
+            Config::default(),
+            TestRng::from_seed(RngAlgorithm::ChaCha, &[7; 32]),
+        );
+
+        for root in &["Tree", "Attrs", "Point", "Step"] {
+            for _ in 0..20 {
+                let value = schema
+                    .values_of(root)
+                    .new_tree(&mut runner)
+                    .unwrap()
+                    .current();
+                assert_eq!(schema.validate(root, &value), Ok(()), "{:?}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn limits_bound_sizes_and_nodes() {
+        let schema = Schema::from_dsl("type Words [String]\n\ntype Tree [Tree]\n").unwrap();
+        let mut runner = TestRunner::deterministic();
+        let limits = Limits {
+            max_depth: 100,
+            collection_size: 3..=3,
+            string_length: 5..=5,
+            max_nodes: 10,
+        };
+
+        let words = schema
+            .values_within("Words", limits.clone())
+            .new_tree(&mut runner)
+            .unwrap()
+            .current();
+        match words {
+            Value::List(words) => {
+                assert_eq!(words.len(), 3);
+                for word in words {
+                    assert!(matches!(word, Value::String(s) if s.chars().count() == 5));
+                }
+            }
+            other => panic!("{:?} isn't a list", other),
+        }
+
+        fn nodes(value: &Value) -> usize {
+            match value {
+                Value::List(items) => 1 + items.iter().map(nodes).sum::<usize>(),
+                _ => 1,
+            }
+        }
+        for max_nodes in 0..20 {
+            let tree = schema
+                .values_within(
+                    "Tree",
+                    Limits {
+                        max_nodes,
+                        ..limits.clone()
+                    },
+                )
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            assert!(nodes(&tree) <= max_nodes.max(1), "{:?}", tree);
+            assert_eq!(schema.validate("Tree", &tree), Ok(()));
+        }
+    }
+}
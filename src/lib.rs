@@ -1,18 +1,38 @@
 #![deny(clippy::all)]
 #![deny(clippy::pedantic)]
 
-use std::{convert::TryInto, fmt, path::PathBuf, str::FromStr};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt,
+    path::PathBuf,
+    str::FromStr,
+};
 
-use proptest::{arbitrary::Arbitrary, strategy::Strategy};
+use proptest::{
+    arbitrary::Arbitrary,
+    strategy::{Strategy, ValueTree},
+};
 
 #[cfg(feature = "build-binary")]
 use structopt::StructOpt;
 
+pub mod block_store;
+pub mod car;
+pub mod cid;
+pub mod codec;
+mod diagnostic;
+mod error;
+pub mod generation;
+mod multibase;
 pub mod schema;
+pub mod validation;
+pub mod value;
+mod varint;
 
-// TODO: clean up unwraps
-
-// TODO: create a suitable error type
+pub use cid::Cid;
+pub use codec::Codec;
+pub use error::Error;
+pub use value::Value;
 
 #[derive(Clone, Copy, test_strategy::Arbitrary)]
 pub struct Seed {
@@ -39,14 +59,10 @@ impl FromStr for Seed {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let inner = {
-            let bytes = base64::decode(s).unwrap();
-            if bytes.len() == 32 {
-                bytes.try_into().unwrap()
-            } else {
-                return Err("invalid input");
-            }
-        };
+        let inner = base64::decode(s)
+            .map_err(|_| "invalid base64")?
+            .try_into()
+            .map_err(|_| "seed must be exactly 32 bytes")?;
 
         Ok(Seed { inner })
     }
@@ -76,6 +92,38 @@ pub enum Command {
         /// Path to IPLD data file to validate against the specified schema
         #[cfg_attr(feature = "build-binary", structopt(parse(from_os_str)))]
         data_file: Option<PathBuf>,
+
+        /// Codec the data file is encoded with: dag-json or dag-cbor
+        ///
+        /// DAG-CBOR data must be in canonical form.
+        #[cfg_attr(
+            feature = "build-binary",
+            structopt(long, default_value = "dag-json", parse(try_from_str))
+        )]
+        codec: Codec,
+
+        /// Directory of blocks, each in a file named by its CID, to follow links in the data through
+        ///
+        /// If unspecified, links aren't followed.
+        #[cfg_attr(feature = "build-binary", structopt(long, parse(from_os_str)))]
+        blocks: Option<PathBuf>,
+
+        /// How many links deep to follow links through the blocks directory or CAR archive
+        #[cfg_attr(feature = "build-binary", structopt(long, default_value = "16"))]
+        link_depth: usize,
+
+        /// Path to a CAR version 1 archive whose root blocks to validate, following links through its other blocks
+        #[cfg_attr(
+            feature = "build-binary",
+            structopt(long, parse(from_os_str), conflicts_with_all = &["data-file", "blocks"])
+        )]
+        car: Option<PathBuf>,
+
+        /// Type the data or CAR root blocks must be values of
+        ///
+        /// If unspecified, the only type no other type refers to is used.
+        #[cfg_attr(feature = "build-binary", structopt(long = "type"))]
+        type_name: Option<String>,
     },
     /// Generates IPLD schemas and data
     Generate {
@@ -90,9 +138,102 @@ pub enum Command {
         /// If unspecified, generates a schema instead of data.
         #[cfg_attr(feature = "build-binary", structopt(parse(from_os_str)))]
         schema_file: Option<PathBuf>,
+
+        /// Codec to encode generated data with: dag-json or dag-cbor
+        #[cfg_attr(
+            feature = "build-binary",
+            structopt(long, default_value = "dag-json", parse(try_from_str))
+        )]
+        codec: Codec,
+
+        /// Type to generate a value of
+        ///
+        /// If unspecified, the only type no other type refers to is used.
+        #[cfg_attr(feature = "build-binary", structopt(long = "type"))]
+        type_name: Option<String>,
+
+        /// Bounds on the size of generated data
+        #[cfg_attr(feature = "build-binary", structopt(flatten))]
+        limits: DataLimits,
     },
 }
 
+/// Bounds on the size of generated data, each defaulting to that of [`generation::Limits::default`]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "build-binary", derive(StructOpt))]
+// These limits are flattened into `generate`, whose about text would otherwise be replaced by the doc comment above
+#[cfg_attr(
+    feature = "build-binary",
+    structopt(about = "Generates IPLD schemas and data")
+)]
+pub struct DataLimits {
+    /// Levels of nested collections, structs and unions after which data is kept as small as the schema allows
+    /// [default: 4]
+    #[cfg_attr(feature = "build-binary", structopt(long))]
+    pub max_depth: Option<usize>,
+
+    /// Fewest entries in each generated list, or in each map unless keys collide [default: 0]
+    #[cfg_attr(feature = "build-binary", structopt(long))]
+    pub min_collection_size: Option<usize>,
+
+    /// Most entries in each generated map or list [default: 10]
+    #[cfg_attr(feature = "build-binary", structopt(long))]
+    pub max_collection_size: Option<usize>,
+
+    /// Fewest characters in each generated string, or bytes in each bytes value [default: 0]
+    #[cfg_attr(feature = "build-binary", structopt(long))]
+    pub min_string_length: Option<usize>,
+
+    /// Most characters in each generated string, or bytes in each bytes value [default: 32]
+    #[cfg_attr(feature = "build-binary", structopt(long))]
+    pub max_string_length: Option<usize>,
+
+    /// Values, including nested ones, after which data is kept as small as the schema allows [default: 1000]
+    #[cfg_attr(feature = "build-binary", structopt(long))]
+    pub max_nodes: Option<usize>,
+}
+
+impl TryFrom<&DataLimits> for generation::Limits {
+    type Error = Error;
+
+    /// Fills in the defaults of unspecified limits, failing with [`Error::Usage`] if a minimum is above its maximum
+    fn try_from(limits: &DataLimits) -> Result<Self, Error> {
+        let default = Self::default();
+        let range = |name: &str,
+                     min: Option<usize>,
+                     max: Option<usize>,
+                     default: std::ops::RangeInclusive<usize>| {
+            let (min, max) = (
+                min.unwrap_or(*default.start()),
+                max.unwrap_or(*default.end()),
+            );
+            if min > max {
+                return Err(Error::Usage(format!(
+                    "--min-{0} {1} is greater than --max-{0} {2}",
+                    name, min, max
+                )));
+            }
+            Ok(min..=max)
+        };
+        Ok(Self {
+            max_depth: limits.max_depth.unwrap_or(default.max_depth),
+            collection_size: range(
+                "collection-size",
+                limits.min_collection_size,
+                limits.max_collection_size,
+                default.collection_size,
+            )?,
+            string_length: range(
+                "string-length",
+                limits.min_string_length,
+                limits.max_string_length,
+                default.string_length,
+            )?,
+            max_nodes: limits.max_nodes.unwrap_or(default.max_nodes),
+        })
+    }
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "build-binary", derive(StructOpt))]
 #[cfg_attr(feature = "build-binary", structopt(name = env!("CARGO_PKG_NAME"), version = env!("CARGO_PKG_VERSION"), author = env!("CARGO_PKG_AUTHORS"), about = env!("CARGO_PKG_DESCRIPTION")))]
@@ -105,9 +246,13 @@ pub struct Opt {
     cmd: Command,
 }
 
-#[allow(clippy::result_unit_err)]
-#[allow(clippy::missing_errors_doc)]
-pub fn run<W: std::io::Write>(opt: Opt, output: &mut W) -> Result<(), ()> {
+/// Runs the command described by `opt`, writing any output to `output`
+///
+/// # Errors
+///
+/// Fails if an input can't be read, output can't be written, or if a schema or data file is invalid.
+/// Each category of failure has its own [`Error::exit_code`].
+pub fn run<W: std::io::Write>(opt: Opt, output: &mut W) -> Result<(), Error> {
     if opt.dump_args {
         writeln!(
             output,
@@ -116,57 +261,268 @@ pub fn run<W: std::io::Write>(opt: Opt, output: &mut W) -> Result<(), ()> {
                 dump_args: false,
                 ..opt
             }
-        )
-        .unwrap();
+        )?;
         return Ok(());
     }
 
     match &opt.cmd {
+        Command::Validate {
+            schema_file,
+            link_depth,
+            car: Some(car),
+            type_name,
+            ..
+        } => validate_car(schema_file, car, type_name.as_deref(), *link_depth, output),
         Command::Validate {
             schema_file,
             data_file,
-        } => validate(schema_file, data_file, output),
-        Command::Generate { seed, schema_file } => {
-            generate(&seed.unwrap_or_default(), schema_file, output)
+            codec,
+            blocks,
+            link_depth,
+            car: None,
+            type_name,
+        } => {
+            let store = blocks.as_ref().map(block_store::DirectoryStore::new);
+            let links = store.as_ref().map(|store| validation::Links {
+                store,
+                max_depth: *link_depth,
+            });
+            validate(
+                schema_file,
+                data_file,
+                *codec,
+                type_name.as_deref(),
+                links,
+                output,
+            )
         }
+        Command::Generate {
+            seed,
+            schema_file,
+            codec,
+            type_name,
+            limits,
+        } => generate(
+            &seed.unwrap_or_default(),
+            schema_file,
+            *codec,
+            type_name.as_deref(),
+            &limits.try_into()?,
+            output,
+        ),
     }
 }
 
 fn validate<P: AsRef<std::path::Path> + std::fmt::Debug, W: std::io::Write>(
     schema_file: &P,
     data_file: &Option<P>,
+    codec: Codec,
+    root: Option<&str>,
+    links: Option<validation::Links<'_>>,
     out: &mut W,
-) -> Result<(), ()> {
+) -> Result<(), Error> {
     match data_file {
         None => validate_schema(schema_file, out),
-        Some(data) => validate_data(schema_file, data, out),
+        Some(data) => validate_data(schema_file, data, codec, root, links, out),
     }
 }
 
 fn validate_schema<P: AsRef<std::path::Path> + std::fmt::Debug, W: std::io::Write>(
     schema_file: &P,
     _out: &mut W,
-) -> Result<(), ()> {
-    schema::schema_dsl::parse(&std::fs::read_to_string(schema_file).unwrap()).unwrap();
+) -> Result<(), Error> {
+    read_schema(schema_file)?;
     // TODO: write
     Ok(())
 }
 
+fn read_schema<P: AsRef<std::path::Path>>(schema_file: &P) -> Result<schema::Schema, Error> {
+    let path = schema_file.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|source| Error::Io {
+        path: Some(path.to_path_buf()),
+        source,
+    })?;
+
+    let schema = schema::schema_dsl::parse(&text).map_err(|source| Error::Parse {
+        path: path.to_path_buf(),
+        text,
+        source,
+    })?;
+
+    schema.check().map_err(|problems| Error::Schema {
+        path: path.to_path_buf(),
+        problems,
+    })?;
+    Ok(schema)
+}
+
 fn validate_data<P: AsRef<std::path::Path> + std::fmt::Debug, W: std::io::Write>(
     schema_file: &P,
     data_file: &P,
+    codec: Codec,
+    root: Option<&str>,
+    links: Option<validation::Links<'_>>,
     _out: &mut W,
-) -> Result<(), ()> {
-    validate_schema(schema_file, &mut std::io::sink())?;
+) -> Result<(), Error> {
+    let schema = read_schema(schema_file)?;
+    let root = root_type(&schema, schema_file, root)?;
+    let data = read_data(data_file, codec)?;
 
-    todo!(
-        "validate data ({:?}) using schema ({:?})",
-        data_file,
-        schema_file
-    );
+    schema
+        .validate_linked(root, &data, links)
+        .map_err(|problems| Error::Data {
+            path: data_file.as_ref().to_path_buf(),
+            problems,
+        })
 }
 
-fn generate<P, W>(seed: &Seed, schema_file: &Option<P>, out: &mut W) -> Result<(), ()>
+fn read_data<P: AsRef<std::path::Path>>(data_file: &P, codec: Codec) -> Result<Value, Error> {
+    let path = data_file.as_ref();
+    let bytes = std::fs::read(path).map_err(|source| Error::Io {
+        path: Some(path.to_path_buf()),
+        source,
+    })?;
+
+    let decoded = match codec {
+        Codec::DagJson => {
+            codec::dag_json::decode(skip_header(&bytes)).map_err(|err| err.to_string())
+        }
+        Codec::DagCbor => codec::dag_cbor::decode_strict(&bytes).map_err(|err| err.to_string()),
+    };
+    decoded.map_err(|message| Error::Decode {
+        path: path.to_path_buf(),
+        message,
+    })
+}
+
+/// Validates each root block of a CAR archive, writing whether each block in the archive is valid, invalid,
+/// unreachable from the roots, or not visited because it's deeper than `max_depth`, and listing blocks which are linked
+/// to but missing
+fn validate_car<P: AsRef<std::path::Path> + std::fmt::Debug, W: std::io::Write>(
+    schema_file: &P,
+    car_file: &P,
+    root: Option<&str>,
+    max_depth: usize,
+    out: &mut W,
+) -> Result<(), Error> {
+    let schema = read_schema(schema_file)?;
+    let root = root_type(&schema, schema_file, root)?;
+    let path = car_file.as_ref();
+    let bytes = std::fs::read(path).map_err(|source| Error::Io {
+        path: Some(path.to_path_buf()),
+        source,
+    })?;
+    let car = car::Car::read(&bytes).map_err(|err| Error::Decode {
+        path: path.to_path_buf(),
+        message: err.to_string(),
+    })?;
+
+    let store = VisitLog {
+        store: &car,
+        visited: std::cell::RefCell::new(std::collections::BTreeSet::new()),
+    };
+    let mut problems = vec![];
+    for cid in car.roots() {
+        let links = validation::Links {
+            store: &store,
+            max_depth,
+        };
+        if let Err(mismatches) = schema.validate_block(root, cid, links) {
+            problems.extend(mismatches);
+        }
+    }
+
+    let visited = store.visited.into_inner();
+    let unreachable: std::collections::BTreeSet<&Cid> = car.unreachable().into_iter().collect();
+    let invalid: std::collections::BTreeSet<&Cid> = problems
+        .iter()
+        .filter_map(|problem| problem.block.as_ref())
+        .collect();
+    let mut reported = std::collections::BTreeSet::new();
+    for cid in car.cids().filter(|cid| reported.insert(*cid)) {
+        let status = if unreachable.contains(cid) {
+            "unreachable"
+        } else if !visited.contains(cid) {
+            "not visited"
+        } else if invalid.contains(cid) {
+            "invalid"
+        } else {
+            "valid"
+        };
+        writeln!(out, "{} {}", cid, status)?;
+    }
+    for cid in visited.iter().filter(|cid| !reported.contains(cid)) {
+        writeln!(out, "{} missing", cid)?;
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Data {
+            path: path.to_path_buf(),
+            problems,
+        })
+    }
+}
+
+/// Block store recording the CID of every block fetched from it, whether or not it has the block
+struct VisitLog<'s> {
+    store: &'s dyn block_store::BlockStore,
+    visited: std::cell::RefCell<std::collections::BTreeSet<Cid>>,
+}
+
+impl block_store::BlockStore for VisitLog<'_> {
+    fn get(&self, cid: &Cid) -> std::io::Result<Option<Vec<u8>>> {
+        self.visited.borrow_mut().insert(cid.clone());
+        self.store.get(cid)
+    }
+}
+
+/// Skips the `##` comment lines heading generated DAG-JSON data files
+fn skip_header(mut bytes: &[u8]) -> &[u8] {
+    while bytes.starts_with(b"#") {
+        let line_end = bytes
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(bytes.len(), |i| i + 1);
+        bytes = &bytes[line_end..];
+    }
+    bytes
+}
+
+/// Type which data is generated as or validated against: the one given, or else the schema's only root
+fn root_type<'s, P: AsRef<std::path::Path>>(
+    schema: &'s schema::Schema,
+    schema_file: &P,
+    given: Option<&'s str>,
+) -> Result<&'s str, Error> {
+    let roots = schema.roots();
+    match (given, roots.as_slice()) {
+        (Some(name), _) if schema.resolves(name) => Ok(name),
+        (None, [root]) => Ok(root.as_str()),
+        _ => {
+            let candidates = if given.is_some() || roots.is_empty() {
+                schema.types().names().collect()
+            } else {
+                roots
+            };
+            Err(Error::RootType {
+                path: schema_file.as_ref().to_path_buf(),
+                given: given.map(ToString::to_string),
+                candidates: candidates.into_iter().cloned().collect(),
+            })
+        }
+    }
+}
+
+fn generate<P, W>(
+    seed: &Seed,
+    schema_file: &Option<P>,
+    codec: Codec,
+    type_name: Option<&str>,
+    limits: &generation::Limits,
+    out: &mut W,
+) -> Result<(), Error>
 where
     P: AsRef<std::path::Path> + std::fmt::Debug,
     W: std::io::Write,
@@ -175,38 +531,40 @@ where
 
     match schema_file {
         None => generate_schema(seed, &mut out),
-        Some(schema) => generate_data(seed, schema, &mut out),
+        Some(schema) => generate_data(seed, schema, codec, type_name, limits, &mut out),
     }
 }
 
 // TODO: dump args in header comments
 
-fn generate_schema<W: std::io::Write>(seed: &Seed, out: &mut W) -> Result<(), ()> {
+/// Test runner whose random number generator is seeded by `seed`, so generation from it is deterministic
+fn seeded_runner(seed: &Seed) -> proptest::test_runner::TestRunner {
     let config = proptest::test_runner::Config::default();
     let rng = proptest::test_runner::TestRng::from_seed(
         proptest::test_runner::RngAlgorithm::ChaCha,
         &seed.inner,
     );
-    let mut runner = proptest::test_runner::TestRunner::new_with_rng(config, rng);
+    proptest::test_runner::TestRunner::new_with_rng(config, rng)
+}
 
+fn generate_schema<W: std::io::Write>(seed: &Seed, out: &mut W) -> Result<(), Error> {
     let schema = schema::Schema::arbitrary()
-        .new_tree(&mut runner)
-        .unwrap()
+        .new_tree(&mut seeded_runner(seed))
+        .expect("schema strategy never rejects")
         .current();
 
-    writeln!(out, "##").unwrap();
+    writeln!(out, "##")?;
     writeln!(
         out,
         "## Deterministically generated with {} {}",
         env!("CARGO_PKG_NAME"),
         env!("CARGO_PKG_VERSION")
-    )
-    .unwrap();
-    writeln!(out, "##").unwrap();
-    writeln!(out, "##   - reproduction seed: '{}'", seed).unwrap();
-    writeln!(out, "##").unwrap();
-    writeln!(out).unwrap();
-    writeln!(out, "{}", schema).unwrap();
+    )?;
+    writeln!(out, "##")?;
+    writeln!(out, "##   - reproduction seed: '{}'", seed)?;
+    writeln!(out, "##")?;
+    writeln!(out)?;
+    writeln!(out, "{}", schema)?;
 
     Ok(())
 }
@@ -214,35 +572,69 @@ fn generate_schema<W: std::io::Write>(seed: &Seed, out: &mut W) -> Result<(), ()
 fn generate_data<P: AsRef<std::path::Path> + std::fmt::Debug, W: std::io::Write>(
     seed: &Seed,
     schema_file: &P,
+    codec: Codec,
+    type_name: Option<&str>,
+    limits: &generation::Limits,
     out: &mut W,
-) -> Result<(), ()> {
-    validate_schema(schema_file, &mut std::io::sink())?;
+) -> Result<(), Error> {
+    let schema = read_schema(schema_file)?;
+    let root = root_type(&schema, schema_file, type_name)?;
+    let data = schema
+        .values_within(root, limits.clone())
+        .new_tree(&mut seeded_runner(seed))
+        .expect("data strategy never rejects")
+        .current();
 
-    writeln!(out, "##").unwrap();
+    match codec {
+        // DAG-CBOR is binary, so only DAG-JSON output is headed with comments
+        Codec::DagJson => {
+            write_data_header(seed, schema_file, root, limits, out)?;
+            let json = codec::dag_json::encode(&data).expect("generated floats are finite");
+            out.write_all(&json)?;
+            writeln!(out)?;
+        }
+        Codec::DagCbor => {
+            let cbor = codec::dag_cbor::encode(&data).expect("generated floats are finite");
+            out.write_all(&cbor)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_data_header<P: std::fmt::Debug, W: std::io::Write>(
+    seed: &Seed,
+    schema_file: &P,
+    type_name: &str,
+    limits: &generation::Limits,
+    out: &mut W,
+) -> Result<(), Error> {
+    writeln!(out, "##")?;
     writeln!(
         out,
         "## Deterministically generated with {} {}",
         env!("CARGO_PKG_NAME"),
         env!("CARGO_PKG_VERSION")
-    )
-    .unwrap();
-    writeln!(out, "##").unwrap();
-    writeln!(out, "##   - reproduction seed: '{}'", seed).unwrap();
-    writeln!(out, "##   - schema file: {:?}", schema_file).unwrap(); // TODO: consider emitting a CID for the schema file's contents too
-    writeln!(out, "##").unwrap();
-    writeln!(out).unwrap();
-
-    todo!(
-        "generate data using seed '{}' and schema {:?}",
-        seed,
-        schema_file
-    );
+    )?;
+    writeln!(out, "##")?;
+    writeln!(out, "##   - reproduction seed: '{}'", seed)?;
+    writeln!(out, "##   - schema file: {:?}", schema_file)?; // TODO: consider emitting a CID for the schema file's contents too
+    writeln!(out, "##   - type: {}", type_name)?;
+    writeln!(
+        out,
+        "##   - limits: depth {}, collection size {:?}, string length {:?}, nodes {}",
+        limits.max_depth, limits.collection_size, limits.string_length, limits.max_nodes
+    )?;
+    writeln!(out, "##")?;
+    writeln!(out)?;
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::path::Path;
+
     use test_strategy::proptest;
 
     #[cfg(feature = "fast-test")]
@@ -257,71 +649,384 @@ mod tests {
 
     use insta::assert_debug_snapshot;
 
+    /// Creates a temporary file holding `contents`
+    fn temp_file(contents: &[u8]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, contents).unwrap();
+        file
+    }
+
+    /// The `validate` command for `schema_file` and `data_file` with every other option at its default
+    fn validate_command(schema_file: &Path, data_file: Option<&Path>) -> Command {
+        Command::Validate {
+            schema_file: schema_file.into(),
+            data_file: data_file.map(Into::into),
+            codec: Codec::DagJson,
+            blocks: None,
+            link_depth: 16,
+            car: None,
+            type_name: None,
+        }
+    }
+
+    /// Validates `data`, or just `schema` if there's no data, with every other option at its default
+    fn validate_files(schema: &[u8], data: Option<&[u8]>) -> Result<(), Error> {
+        let schema_file = temp_file(schema);
+        let data_file = data.map(temp_file);
+        run(
+            Opt {
+                dump_args: false,
+                cmd: validate_command(
+                    schema_file.path(),
+                    data_file.as_ref().map(tempfile::NamedTempFile::path),
+                ),
+            },
+            &mut std::io::sink(),
+        )
+    }
+
     #[test]
     fn snapshot_of_fixed_seed() {
         assert_debug_snapshot!(Seed::fixed());
     }
 
     #[test]
-    #[cfg(not(feature = "fast-test"))]
-    fn snapshot_of_schema_generated_from_fixed_seed() {
-        let seed = Some(Seed::fixed());
+    fn malformed_seeds_are_rejected() {
+        assert!("not base64!".parse::<Seed>().is_err());
+        assert!(base64::encode([0_u8; 31]).parse::<Seed>().is_err());
+    }
 
-        let mut schema_buffer = std::io::Cursor::new(vec![]);
+    #[test]
+    fn missing_schema_file_is_an_io_error() {
+        let err = run(
+            Opt {
+                dump_args: false,
+                cmd: validate_command(Path::new("does/not/exist.ipldsch"), None),
+            },
+            &mut std::io::sink(),
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, Error::Io { path: Some(_), .. }));
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    #[test]
+    fn malformed_schema_is_a_parse_error() {
+        let err = validate_files(b"type Foo nope\n", None).unwrap_err();
+
+        assert!(matches!(err, Error::Parse { .. }));
+        assert_eq!(err.exit_code(), 3);
+    }
+
+    #[test]
+    fn unresolved_reference_is_a_schema_error() {
+        let err = validate_files(b"type Foo {String:Missing}\n", None).unwrap_err();
+
+        assert!(matches!(err, Error::Schema { .. }));
+        assert_eq!(err.exit_code(), 4);
+    }
+
+    #[test]
+    fn nonconforming_data_is_a_data_error() {
+        let err = validate_files(b"type Foo [Int]\n", Some(b"[1, \"two\"]")).unwrap_err();
+
+        assert!(matches!(err, Error::Data { .. }));
+        assert_eq!(err.exit_code(), 5);
+        assert!(err
+            .to_string()
+            .ends_with("\n  - /1: found string, expected `Int`"));
+    }
+
+    #[test]
+    fn car_roots_are_validated_and_each_block_reported() {
+        let schema_file = temp_file(
+            b"type Node struct {\n  n Int\n}\n\ntype NodeLink &Node\n\ntype Root [NodeLink]\n",
+        );
+
+        let block = |json: &str| {
+            (
+                Cid::new_v1(cid::DAG_JSON, json.as_bytes()),
+                json.as_bytes().to_vec(),
+            )
+        };
+        let (good, good_block) = block(r#"{"n": 1}"#);
+        let (bad, bad_block) = block(r#"{"n": "x"}"#);
+        let (missing, _) = block(r#"{"n": 2}"#);
+        let (stray, stray_block) = block("null");
+        let (root, root_block) = block(&format!(
+            r#"[{{"/": "{}"}}, {{"/": "{}"}}, {{"/": "{}"}}]"#,
+            good, bad, missing
+        ));
+        let mut car = car::Car::new(vec![root.clone()]);
+        car.insert(root.clone(), root_block);
+        car.insert(good.clone(), good_block);
+        car.insert(bad.clone(), bad_block);
+        car.insert(stray.clone(), stray_block);
+        let mut car_file = tempfile::NamedTempFile::new().unwrap();
+        car.write(&mut car_file).unwrap();
+
+        let mut cmd = validate_command(schema_file.path(), None);
+        if let Command::Validate { car, type_name, .. } = &mut cmd {
+            *car = Some(car_file.path().into());
+            *type_name = Some("Root".to_string());
+        }
+        let mut output = vec![];
+        let err = run(
+            Opt {
+                dump_args: false,
+                cmd,
+            },
+            &mut output,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "{} invalid\n{} valid\n{} invalid\n{} unreachable\n{} missing\n",
+                root, good, bad, stray, missing
+            )
+        );
+        assert_eq!(err.exit_code(), 5);
+        assert!(err.to_string().ends_with(&format!(
+            "\n  - /n in block {}: found string, expected `Int`\n  - /2 in block {}: linked block {} is missing, expected `NodeLink`",
+            bad, root, missing
+        )));
+    }
+
+    /// A type to generate data of, since generated schemas may have several roots
+    fn some_root(schema_file: &tempfile::NamedTempFile) -> String {
+        let schema = read_schema(&schema_file.path()).unwrap();
+        schema
+            .roots()
+            .first()
+            .map_or_else(|| "Any".to_string(), ToString::to_string)
+    }
+
+    #[test]
+    fn type_defaults_to_the_only_root_and_is_recorded_in_the_header() {
+        let schema_file = temp_file(b"type Foo [Bar]\n\ntype Bar bool\n");
+        let generate = |type_name: Option<&str>| {
+            let mut output = vec![];
+            run(
+                Opt {
+                    dump_args: false,
+                    cmd: Command::Generate {
+                        seed: Some(Seed::fixed()),
+                        schema_file: Some(schema_file.path().into()),
+                        codec: Codec::DagJson,
+                        type_name: type_name.map(ToString::to_string),
+                        limits: DataLimits::default(),
+                    },
+                },
+                &mut output,
+            )
+            .map(|()| String::from_utf8(output).unwrap())
+        };
+
+        assert!(generate(None).unwrap().contains("\n##   - type: Foo\n"));
+        assert!(generate(Some("Bar"))
+            .unwrap()
+            .contains("\n##   - type: Bar\n"));
+
+        let err = generate(Some("Baz")).unwrap_err();
+        assert_eq!(err.exit_code(), 6);
+        assert!(err
+            .to_string()
+            .ends_with("; pass --type with one of `Bar`, `Foo`"));
+    }
+
+    #[test]
+    fn ambiguous_type_is_an_error_listing_candidates() {
+        let schema_file = temp_file(b"type Foo [Int]\n\ntype Bar bool\n");
+        let data_file = temp_file(b"true");
+
+        let err = run(
+            Opt {
+                dump_args: false,
+                cmd: validate_command(schema_file.path(), Some(data_file.path())),
+            },
+            &mut std::io::sink(),
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, Error::RootType { given: None, .. }));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "can't tell which type in {} to use; pass --type with one of `Bar`, `Foo`",
+                schema_file.path().display()
+            )
+        );
+    }
+
+    #[test]
+    fn generated_data_are_within_the_given_limits() {
+        let schema_file = temp_file(b"type Foo [[String]]\n");
+        let mut output = vec![];
         run(
             Opt {
                 dump_args: false,
                 cmd: Command::Generate {
-                    seed,
-                    schema_file: None,
+                    seed: Some(Seed::fixed()),
+                    schema_file: Some(schema_file.path().into()),
+                    codec: Codec::DagJson,
+                    type_name: None,
+                    limits: DataLimits {
+                        max_depth: Some(3),
+                        min_collection_size: Some(2),
+                        max_collection_size: Some(2),
+                        min_string_length: Some(2),
+                        max_string_length: Some(3),
+                        max_nodes: None,
+                    },
                 },
             },
-            &mut schema_buffer,
+            &mut output,
         )
         .unwrap();
 
-        assert_debug_snapshot!(schema::schema_dsl::parse(&String::from_utf8_lossy(
-            &schema_buffer.into_inner()
-        ))
-        .unwrap());
+        assert!(String::from_utf8_lossy(&output).contains(
+            "\n##   - limits: depth 3, collection size 2..=2, string length 2..=3, nodes 1000\n"
+        ));
+        let lists = match codec::dag_json::decode(skip_header(&output)).unwrap() {
+            Value::List(lists) => lists,
+            value => panic!("expected a list, got {:?}", value),
+        };
+        assert_eq!(lists.len(), 2);
+        for list in lists {
+            let strings = match list {
+                Value::List(strings) => strings,
+                value => panic!("expected a list, got {:?}", value),
+            };
+            assert_eq!(strings.len(), 2);
+            for string in strings {
+                match string {
+                    Value::String(s) => assert!((2..=3).contains(&s.chars().count()), "{:?}", s),
+                    value => panic!("expected a string, got {:?}", value),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn limits_with_a_minimum_above_the_maximum_are_a_usage_error() {
+        let schema_file = temp_file(b"type Foo [String]\n");
+        let error = run(
+            Opt {
+                dump_args: false,
+                cmd: Command::Generate {
+                    seed: Some(Seed::fixed()),
+                    schema_file: Some(schema_file.path().into()),
+                    codec: Codec::DagJson,
+                    type_name: None,
+                    limits: DataLimits {
+                        min_string_length: Some(3),
+                        max_string_length: Some(1),
+                        ..DataLimits::default()
+                    },
+                },
+            },
+            &mut vec![],
+        )
+        .unwrap_err();
+
+        assert_eq!(error.exit_code(), 1);
+        assert_eq!(
+            error.to_string(),
+            "--min-string-length 3 is greater than --max-string-length 1"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "build-binary")]
+    fn generate_subcommand_is_described_by_its_own_about_text() {
+        let help = |args: &[&str]| Opt::clap().get_matches_from_safe(args).unwrap_err().message;
+
+        let generate = help(&["ipld-schema", "generate", "--help"]);
+        assert!(
+            generate.contains("\nGenerates IPLD schemas and data\n"),
+            "{}",
+            generate
+        );
+        assert!(!generate.contains("Bounds on"), "{}", generate);
+        let top = help(&["ipld-schema", "--help"]);
+        assert!(top.contains("Generates IPLD schemas and data"), "{}", top);
+        assert!(!top.contains("Bounds on"), "{}", top);
+    }
+
+    #[test]
+    fn header_comments_are_skipped_when_reading_data() {
+        assert_eq!(skip_header(b"##\n##   - seed\n\n[1]\n"), b"\n[1]\n");
+        assert_eq!(skip_header(b"[1]"), b"[1]");
     }
 
     #[test]
     #[cfg(not(feature = "fast-test"))]
-    #[ignore]
-    fn snapshot_of_data_generated_from_fixed_seed() {
+    fn snapshot_of_schema_generated_from_fixed_seed() {
         let seed = Some(Seed::fixed());
 
-        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        let mut schema_buffer = std::io::Cursor::new(vec![]);
         run(
             Opt {
                 dump_args: false,
                 cmd: Command::Generate {
                     seed,
                     schema_file: None,
+                    codec: Codec::DagJson,
+                    type_name: None,
+                    limits: DataLimits::default(),
                 },
             },
-            &mut schema_file,
+            &mut schema_buffer,
         )
         .unwrap();
 
+        assert_debug_snapshot!(schema::schema_dsl::parse(&String::from_utf8_lossy(
+            &schema_buffer.into_inner()
+        ))
+        .unwrap());
+    }
+
+    #[test]
+    fn snapshot_of_data_generated_from_fixed_seed() {
+        let schema_file = temp_file(
+            concat!(
+                "type Order struct {\n  id Int\n  items [Item]\n  status Status\n  customer optional Customer\n  notes {String:String}\n}\n\n",
+                "type Item union {\n  | Product \"product\"\n  | Service \"service\"\n} representation keyed\n\n",
+                "type Product struct {\n  name String\n  price Float\n}\n\n",
+                "type Service struct {\n  hours Int\n  remote Bool\n} representation tuple\n\n",
+                "type Status enum {\n  | pending\n  | shipped\n}\n\n",
+                "type Customer struct {\n  name String\n  tags {String:Bool}\n}\n",
+            )
+            .as_bytes(),
+        );
+
         let mut data_buffer = std::io::Cursor::new(vec![]);
         run(
             Opt {
                 dump_args: false,
                 cmd: Command::Generate {
-                    seed,
+                    seed: Some(Seed::fixed()),
                     schema_file: Some(schema_file.path().into()),
+                    codec: Codec::DagJson,
+                    type_name: Some("Order".to_string()),
+                    limits: DataLimits {
+                        min_collection_size: Some(1),
+                        max_collection_size: Some(3),
+                        max_string_length: Some(6),
+                        ..DataLimits::default()
+                    },
                 },
             },
             &mut data_buffer,
         )
         .unwrap();
 
-        assert_debug_snapshot!(schema::schema_dsl::parse(&String::from_utf8_lossy(
-            &data_buffer.into_inner()
-        ))
-        .unwrap());
+        assert_debug_snapshot!(
+            codec::dag_json::decode(skip_header(&data_buffer.into_inner())).unwrap()
+        );
     }
 
     #[proptest(cases = CASES, max_shrink_iters = MAX_SHRINK_ITERS)]
@@ -333,6 +1038,9 @@ mod tests {
                 cmd: Command::Generate {
                     seed: Some(seed),
                     schema_file: None,
+                    codec: Codec::DagJson,
+                    type_name: None,
+                    limits: DataLimits::default(),
                 },
             },
             &mut schema_file,
@@ -343,10 +1051,7 @@ mod tests {
         run(
             Opt {
                 dump_args: false,
-                cmd: Command::Validate {
-                    schema_file: schema_file.path().into(),
-                    data_file: None,
-                },
+                cmd: validate_command(schema_file.path(), None),
             },
             &mut output,
         )
@@ -358,7 +1063,6 @@ mod tests {
     }
 
     #[proptest(cases = CASES, max_shrink_iters = MAX_SHRINK_ITERS)]
-    #[ignore]
     fn generated_data_are_valid(seed: Seed) {
         let mut schema_file = tempfile::NamedTempFile::new()?;
         run(
@@ -367,11 +1071,15 @@ mod tests {
                 cmd: Command::Generate {
                     seed: Some(seed),
                     schema_file: None,
+                    codec: Codec::DagJson,
+                    type_name: None,
+                    limits: DataLimits::default(),
                 },
             },
             &mut schema_file,
         )
         .unwrap();
+        let type_name = some_root(&schema_file);
 
         let mut data_file = tempfile::NamedTempFile::new()?;
         run(
@@ -380,20 +1088,24 @@ mod tests {
                 cmd: Command::Generate {
                     seed: Some(seed),
                     schema_file: Some(schema_file.path().into()),
+                    codec: Codec::DagJson,
+                    type_name: Some(type_name.clone()),
+                    limits: DataLimits::default(),
                 },
             },
             &mut data_file,
         )
         .unwrap();
 
+        let mut cmd = validate_command(schema_file.path(), Some(data_file.path()));
+        if let Command::Validate { type_name: t, .. } = &mut cmd {
+            *t = Some(type_name);
+        }
         let mut output = std::io::Cursor::new(vec![]);
         run(
             Opt {
                 dump_args: false,
-                cmd: Command::Validate {
-                    schema_file: schema_file.path().into(),
-                    data_file: Some(data_file.path().into()),
-                },
+                cmd,
             },
             &mut output,
         )
@@ -0,0 +1,121 @@
+//! The [multibase](https://github.com/multiformats/multibase) encodings used in the string forms of CIDs
+
+use std::convert::TryFrom;
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encodes bytes as lowercase RFC 4648 base32 without padding, multibase prefix `b`
+pub(crate) fn encode_base32(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut buffer = 0_u16;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | u16::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(char::from(
+                BASE32_ALPHABET[usize::from((buffer >> bits) & 0x1f)],
+            ));
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        out.push(char::from(
+            BASE32_ALPHABET[usize::from((buffer << (5 - bits)) & 0x1f)],
+        ));
+    }
+    out
+}
+
+/// Decodes lowercase RFC 4648 base32 without padding
+pub(crate) fn decode_base32(text: &str) -> Option<Vec<u8>> {
+    let mut out = vec![];
+    let mut buffer = 0_u16;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let digit = BASE32_ALPHABET.iter().position(|&d| d == c)?;
+        buffer = (buffer << 5) | u16::try_from(digit).ok()?;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push(u8::try_from(buffer >> bits).ok()?);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+/// Encodes bytes as base58 with the Bitcoin alphabet, multibase prefix `z`
+pub(crate) fn encode_base58(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    // little-endian base58 digits
+    let mut digits: Vec<u8> = vec![];
+    for &byte in &bytes[zeros..] {
+        let mut carry = u32::from(byte);
+        for digit in &mut digits {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58).to_le_bytes()[0];
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58).to_le_bytes()[0]);
+            carry /= 58;
+        }
+    }
+    std::iter::repeat_n(b'1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|&d| BASE58_ALPHABET[usize::from(d)]),
+        )
+        .map(char::from)
+        .collect()
+}
+
+/// Decodes base58 with the Bitcoin alphabet
+pub(crate) fn decode_base58(text: &str) -> Option<Vec<u8>> {
+    let zeros = text.bytes().take_while(|&c| c == b'1').count();
+    // little-endian bytes
+    let mut bytes: Vec<u8> = vec![];
+    for c in text.bytes().skip(zeros) {
+        let mut carry = u32::try_from(BASE58_ALPHABET.iter().position(|&d| d == c)?).ok()?;
+        for byte in &mut bytes {
+            carry += u32::from(*byte) * 58;
+            *byte = carry.to_le_bytes()[0];
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry.to_le_bytes()[0]);
+            carry >>= 8;
+        }
+    }
+    Some(
+        std::iter::repeat_n(0, zeros)
+            .chain(bytes.into_iter().rev())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn encodings_match_rfc_4648_and_bitcoin() {
+        assert_eq!(encode_base32(b"foobar"), "mzxw6ytboi");
+        assert_eq!(encode_base32(b"f"), "my");
+        assert_eq!(decode_base32("mzxw6ytboi").unwrap(), b"foobar");
+        assert_eq!(decode_base32("MZ"), None);
+
+        assert_eq!(encode_base58(b"hello world"), "StV1DL6CwTryKyV");
+        assert_eq!(encode_base58(&[0, 0, 1]), "112");
+        assert_eq!(decode_base58("StV1DL6CwTryKyV").unwrap(), b"hello world");
+        assert_eq!(decode_base58("112").unwrap(), [0, 0, 1]);
+        assert_eq!(decode_base58("0"), None);
+    }
+}
@@ -1,6 +1,12 @@
 #![allow(dead_code)]
+#![warn(missing_docs)]
 
-use std::fmt;
+//! Abstract syntax tree of [IPLD Schemas](https://specs.ipld.io/schemas/)
+//!
+//! A [`Schema`] can be parsed from its DSL form with [`Schema::from_dsl`] (or [`str::parse`]), read from its reified
+//! JSON form with [`Schema::from_json`], and rendered back to DSL with [`Schema::to_dsl`] (or [`ToString`]).
+
+use std::{borrow::Borrow, fmt, str::FromStr};
 
 use proptest::{collection::btree_map, prelude::*};
 use serde::{Deserialize, Serialize};
@@ -14,65 +20,237 @@ type Int = i64;
 type Float = f64;
 type Map<K, V> = std::collections::BTreeMap<K, V>;
 
-// TODO: revisit public API
+/// Error describing where and why the DSL form of a schema failed to parse
+pub type ParseError = peg::error::ParseError<peg::str::LineCol>;
+
+mod builder;
+mod check;
+mod reference;
+mod repair;
+mod resolve;
 
-// TODO: docs
+pub use builder::{EditError, Representation, SchemaBuilder};
+pub use check::{Problem, SAFE_INT_RANGE};
+pub use reference::{Location, Reference, Segment, PRELUDE};
+pub use resolve::ResolveError;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
 struct Null;
 
+/// Name of a type declared in a schema, e.g. `Foo` in `type Foo struct {}`
 #[derive(
     Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, test_strategy::Arbitrary,
 )]
-pub(crate) struct TypeName(#[strategy("[A-Z][a-z0-9_]*")] String);
+pub struct TypeName(#[strategy("[A-Z][a-z0-9_]*")] String);
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-struct SchemaMap(
+impl TypeName {
+    /// Name as written in the schema
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for TypeName {
+    type Err = ParseError;
+
+    /// Parses a type name, which must start with an uppercase letter
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        schema_dsl::type_name(s)
+    }
+}
+
+impl Borrow<str> for TypeName {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+/// All the types declared in a schema, ordered by name
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, test_strategy::Arbitrary)]
+pub struct SchemaMap(
     // TODO: increase size range
     #[strategy(btree_map(any::<TypeName>(), any::<Type>(), DEFAULT_SIZE_RANGE))]
     Map<TypeName, Type>,
 );
 
+impl SchemaMap {
+    /// Looks up the type declared with `name`
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Type> {
+        self.0.get(name)
+    }
+
+    /// Iterates over the declared types and their definitions, ordered by name
+    pub fn iter(&self) -> std::collections::btree_map::Iter<'_, TypeName, Type> {
+        self.0.iter()
+    }
+
+    /// Names of the declared types, in order
+    pub fn names(&self) -> impl Iterator<Item = &TypeName> {
+        self.0.keys()
+    }
+
+    /// Number of declared types
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no types are declared
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a SchemaMap {
+    type Item = (&'a TypeName, &'a Type);
+    type IntoIter = std::collections::btree_map::Iter<'a, TypeName, Type>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Name of an advanced data layout declared in a schema
 #[derive(
     Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, test_strategy::Arbitrary,
 )]
-pub(crate) struct AdvancedDataLayoutName(String);
+pub struct AdvancedDataLayoutName(String);
+
+impl AdvancedDataLayoutName {
+    /// Name as written in the schema
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
 
+/// All the advanced data layouts declared in a schema, ordered by name
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, test_strategy::Arbitrary)]
-pub(crate) struct AdvancedDataLayoutMap(
+pub struct AdvancedDataLayoutMap(
     #[strategy(Just(Map::new()))] Map<AdvancedDataLayoutName, AdvancedDataLayout>,
 );
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// An IPLD schema: a set of named types
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[derive(test_strategy::Arbitrary)]
-pub(crate) struct Schema {
+pub struct Schema {
+    #[strategy(any::<SchemaMap>().prop_map(repair::well_formed))]
     types: SchemaMap,
     #[serde(default, skip_serializing_if = "is_default")]
     advanced: AdvancedDataLayoutMap,
 }
 
+impl Schema {
+    /// Parses the DSL form of a schema
+    ///
+    /// # Errors
+    ///
+    /// Fails if `dsl` isn't syntactically valid.
+    pub fn from_dsl(dsl: &str) -> Result<Self, ParseError> {
+        schema_dsl::parse(dsl)
+    }
+
+    /// Reads the reified JSON form of a schema
+    ///
+    /// # Errors
+    ///
+    /// Fails if `json` doesn't describe a schema.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Renders the DSL form of the schema
+    #[must_use]
+    pub fn to_dsl(&self) -> String {
+        self.to_string()
+    }
+
+    /// All the types declared in the schema
+    #[must_use]
+    pub fn types(&self) -> &SchemaMap {
+        &self.types
+    }
+
+    /// Looks up the type declared with `name`
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Type> {
+        self.types.get(name)
+    }
+}
+
+impl FromStr for Schema {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_dsl(s)
+    }
+}
+
+/// Definition of a named type
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(tag = "kind", rename_all = "lowercase")]
 #[derive(test_strategy::Arbitrary)]
 // TODO: can't handle some variants until fields referred to by representation exist and field orders matches set of fields
-pub(crate) enum Type {
+pub enum Type {
+    /// Boolean type, `bool`
     Bool(TypeBool),
+    /// String type, `string`
     String(TypeString),
+    /// Bytes type, e.g. `bytes`
     Bytes(TypeBytes),
+    /// Integer type, `int`
     Int(TypeInt),
+    /// Float type, `float`
     Float(TypeFloat),
+    /// Map type, e.g. `{String:Int}`
     Map(TypeMap),
+    /// List type, e.g. `[String]`
     List(TypeList),
+    /// Link type, e.g. `&Foo`
     Link(TypeLink),
+    /// Union type
     Union(TypeUnion),
+    /// Struct type
     #[weight(0)]
     Struct(TypeStruct),
+    /// Enum type
     #[weight(0)]
     Enum(TypeEnum),
+    /// Copy of another type, e.g. `= Foo`
     Copy(TypeCopy),
 }
 
+macro_rules! impl_from_for_type {
+    ($($variant:ident($ty:ty)),* $(,)?) => {
+        $(
+            impl From<$ty> for Type {
+                fn from(ty: $ty) -> Self {
+                    Self::$variant(ty)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_for_type!(
+    Bool(TypeBool),
+    String(TypeString),
+    Bytes(TypeBytes),
+    Int(TypeInt),
+    Float(TypeFloat),
+    Map(TypeMap),
+    List(TypeList),
+    Link(TypeLink),
+    Union(TypeUnion),
+    Struct(TypeStruct),
+    Enum(TypeEnum),
+    Copy(TypeCopy),
+);
+
 /*
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -92,60 +270,98 @@ pub(crate) enum TypeKind {
 }
 */
 
+/// Kinds of the IPLD Data Model which a type can be represented as
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "lowercase")]
 #[derive(test_strategy::Arbitrary)]
-pub(crate) enum RepresentationKind {
+pub enum RepresentationKind {
+    /// Booleans
     Bool,
+    /// Strings
     String,
+    /// Bytes
     Bytes,
+    /// Integers
     Int,
+    /// Floats
     Float,
+    /// Maps
     Map,
+    /// Lists
     List,
+    /// Links
     Link,
 }
 
+/// Literal value of a scalar kind, e.g. an `implicit` default for a struct field
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(untagged)]
 #[derive(test_strategy::Arbitrary)]
-pub(crate) enum AnyScalar {
+pub enum AnyScalar {
+    /// Boolean literal, `true` or `false`
     Bool(bool),
+    /// String literal, e.g. `"foo"`
     String(String),
+    /// Bytes literal, e.g. `x"0a"`
     Bytes(Vec<u8>),
+    /// Integer literal
     Int(Int),
+    /// Float literal
     Float(Float),
 }
 
+/// Declaration of an advanced data layout, e.g. `advanced Rope`
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-struct AdvancedDataLayout;
+pub struct AdvancedDataLayout;
 
+/// The boolean type, `bool`
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-pub(crate) struct TypeBool;
+pub struct TypeBool;
 
+/// The string type, `string`
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-pub(crate) struct TypeString;
+pub struct TypeString;
 
+/// A bytes type, `bytes`, with its representation
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-pub(crate) struct TypeBytes {
+pub struct TypeBytes {
     representation: BytesRepresentation,
 }
 
+impl TypeBytes {
+    /// Creates a bytes type with the given representation
+    #[must_use]
+    pub fn new(representation: BytesRepresentation) -> Self {
+        Self { representation }
+    }
+
+    /// How values are represented in the Data Model
+    #[must_use]
+    pub fn representation(&self) -> &BytesRepresentation {
+        &self.representation
+    }
+}
+
+/// How a bytes type is represented in the Data Model
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "lowercase")]
 #[derive(test_strategy::Arbitrary)]
 // TODO: generate all variants
-enum BytesRepresentation {
+pub enum BytesRepresentation {
+    /// As bytes, the default
     Bytes(bytes_representation::Bytes),
+    /// By the named advanced data layout
     #[weight(0)]
     Advanced(AdvancedDataLayoutName),
 }
 
-mod bytes_representation {
+/// Details of each [`BytesRepresentation`]
+pub mod bytes_representation {
     use serde::{Deserialize, Serialize};
 
+    /// Representation as the bytes kind
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-    pub(crate) struct Bytes;
+    pub struct Bytes;
 }
 
 impl Default for BytesRepresentation {
@@ -154,20 +370,34 @@ impl Default for BytesRepresentation {
     }
 }
 
+/// The integer type, `int`
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-pub(crate) struct TypeInt;
+pub struct TypeInt;
 
+/// The float type, `float`
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-pub(crate) struct TypeFloat;
+pub struct TypeFloat;
+
+fn decode_hex(digits: &str) -> Option<Vec<u8>> {
+    digits
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [_, _] => u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
 
 fn is_default<D: Default + PartialEq>(d: &D) -> bool {
     *d == D::default()
 }
 
+/// A map type, e.g. `{String:Int}`
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[derive(test_strategy::Arbitrary)]
-pub(crate) struct TypeMap {
+pub struct TypeMap {
     key_type: TypeName,
 
     value_type: TypeTerm,
@@ -179,37 +409,84 @@ pub(crate) struct TypeMap {
     representation: MapRepresentation,
 }
 
+impl TypeMap {
+    /// Creates a map type with the default representation
+    pub fn new(key_type: TypeName, value_type: impl Into<TypeTerm>, value_nullable: bool) -> Self {
+        Self {
+            key_type,
+            value_type: value_type.into(),
+            value_nullable,
+            representation: MapRepresentation::default(),
+        }
+    }
+
+    /// Name of the type of keys
+    #[must_use]
+    pub fn key_type(&self) -> &TypeName {
+        &self.key_type
+    }
+
+    /// Type of values
+    #[must_use]
+    pub fn value_type(&self) -> &TypeTerm {
+        &self.value_type
+    }
+
+    /// Whether values may be null
+    #[must_use]
+    pub fn value_nullable(&self) -> bool {
+        self.value_nullable
+    }
+
+    /// How values are represented in the Data Model
+    #[must_use]
+    pub fn representation(&self) -> &MapRepresentation {
+        &self.representation
+    }
+}
+
+/// How a map type is represented in the Data Model
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "lowercase")]
 #[derive(test_strategy::Arbitrary)]
 // TODO: generate all variants
-enum MapRepresentation {
+pub enum MapRepresentation {
+    /// As a map, the default
     Map(map_representation::Map),
+    /// As a string of delimited key-value pairs
     StringPairs(map_representation::StringPairs),
+    /// As a list of key-value pairs
     ListPairs(map_representation::ListPairs),
+    /// By the named advanced data layout
     #[weight(0)]
     Advanced(AdvancedDataLayoutName),
 }
 
-mod map_representation {
+/// Details of each [`MapRepresentation`]
+pub mod map_representation {
     use serde::{Deserialize, Serialize};
 
+    /// Representation as the map kind
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-    pub(crate) struct Map;
+    pub struct Map;
 
+    /// Representation as a string of key-value pairs, e.g. `a=1,b=2`
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     #[derive(test_strategy::Arbitrary)]
-    pub(crate) struct StringPairs {
+    pub struct StringPairs {
+        /// Separator between a key and its value
         #[strategy("[^\"]+")]
-        pub(crate) inner_delim: String,
+        pub inner_delim: String,
 
+        /// Separator between entries
         #[strategy("[^\"]+")]
-        pub(crate) entry_delim: String,
+        pub entry_delim: String,
     }
 
+    /// Representation as a list of two-item lists, each a key and its value
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-    pub(crate) struct ListPairs;
+    pub struct ListPairs;
 }
 
 impl Default for MapRepresentation {
@@ -218,10 +495,11 @@ impl Default for MapRepresentation {
     }
 }
 
+/// A list type, e.g. `[String]`
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[derive(test_strategy::Arbitrary)]
-pub(crate) struct TypeList {
+pub struct TypeList {
     value_type: TypeTerm,
 
     #[serde(default, skip_serializing_if = "is_default")]
@@ -231,18 +509,52 @@ pub(crate) struct TypeList {
     representation: ListRepresentation,
 }
 
+impl TypeList {
+    /// Creates a list type with the default representation
+    pub fn new(value_type: impl Into<TypeTerm>, value_nullable: bool) -> Self {
+        Self {
+            value_type: value_type.into(),
+            value_nullable,
+            representation: ListRepresentation::default(),
+        }
+    }
+
+    /// Type of values
+    #[must_use]
+    pub fn value_type(&self) -> &TypeTerm {
+        &self.value_type
+    }
+
+    /// Whether values may be null
+    #[must_use]
+    pub fn value_nullable(&self) -> bool {
+        self.value_nullable
+    }
+
+    /// How values are represented in the Data Model
+    #[must_use]
+    pub fn representation(&self) -> &ListRepresentation {
+        &self.representation
+    }
+}
+
+/// How a list type is represented in the Data Model
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-enum ListRepresentation {
+pub enum ListRepresentation {
+    /// As a list, the default
     List(list_representation::List),
+    /// By the named advanced data layout
     #[weight(0)]
     Advanced(AdvancedDataLayoutName),
 }
 
-mod list_representation {
+/// Details of each [`ListRepresentation`]
+pub mod list_representation {
     use serde::{Deserialize, Serialize};
 
+    /// Representation as the list kind
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-    pub(crate) struct List;
+    pub struct List;
 }
 
 impl Default for ListRepresentation {
@@ -251,14 +563,31 @@ impl Default for ListRepresentation {
     }
 }
 
+/// A link type, e.g. `&Foo`
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[derive(test_strategy::Arbitrary)]
-pub(crate) struct TypeLink {
+pub struct TypeLink {
     #[strategy("[A-Z][a-z0-9_]*")]
     expected_type: String,
 }
 
+impl TypeLink {
+    /// Creates a link to values of `expected_type`
+    #[must_use]
+    pub fn new(expected_type: TypeName) -> Self {
+        Self {
+            expected_type: expected_type.0,
+        }
+    }
+
+    /// Name of the type the link is expected to point to, `Any` if unconstrained
+    #[must_use]
+    pub fn expected_type(&self) -> &str {
+        &self.expected_type
+    }
+}
+
 impl Default for TypeLink {
     fn default() -> Self {
         Self {
@@ -267,91 +596,194 @@ impl Default for TypeLink {
     }
 }
 
+/// A union type, whose members are listed by its representation
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[derive(test_strategy::Arbitrary)]
-pub(crate) struct TypeUnion {
+pub struct TypeUnion {
     representation: UnionRepresentation,
 }
 
+impl TypeUnion {
+    /// Creates a union type with the given representation, which lists its members
+    #[must_use]
+    pub fn new(representation: UnionRepresentation) -> Self {
+        Self { representation }
+    }
+
+    /// How values are represented in the Data Model, including the table of members
+    #[must_use]
+    pub fn representation(&self) -> &UnionRepresentation {
+        &self.representation
+    }
+
+    /// Names of the union's member types
+    #[must_use]
+    pub fn members(&self) -> Vec<&TypeName> {
+        self.representation.members()
+    }
+}
+
+/// How a union type is represented in the Data Model, including which member types it has
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "lowercase")]
 #[derive(test_strategy::Arbitrary)]
-enum UnionRepresentation {
+pub enum UnionRepresentation {
+    /// By the kind each member is represented as
     Kinded(union_representation::Kinded),
+    /// As a single-entry map from the member's key to its value
     Keyed(union_representation::Keyed),
+    /// As a map with the member's discriminant and its value under separate keys
     Envelope(union_representation::Envelope),
+    /// As the member's map with its discriminant added under a key
     Inline(union_representation::Inline),
+    /// As the member's bytes prefixed with its discriminant byte
     BytePrefix(union_representation::BytePrefix),
 }
 
-mod union_representation {
+impl UnionRepresentation {
+    /// Names of the union's member types, in the order of the representation's table
+    #[must_use]
+    pub fn members(&self) -> Vec<&TypeName> {
+        match self {
+            Self::Kinded(k) => k.0.values().collect(),
+            Self::Keyed(k) => k.0.values().collect(),
+            Self::Envelope(e) => e.discriminant_table.values().collect(),
+            Self::Inline(i) => i.discriminant_table.values().collect(),
+            Self::BytePrefix(b) => b.discriminant_table.keys().collect(),
+        }
+    }
+}
+
+/// Details of each [`UnionRepresentation`]
+pub mod union_representation {
     use super::{Map, RepresentationKind, TypeName, DEFAULT_SIZE_RANGE};
     use proptest::{collection::btree_map, prelude::any};
     use serde::{Deserialize, Serialize};
 
+    /// Member types by the kind they're represented as
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-    pub(crate) struct Kinded(pub(crate) Map<RepresentationKind, TypeName>);
+    pub struct Kinded(pub Map<RepresentationKind, TypeName>);
 
+    /// Member types by the key of their single-entry map
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-    pub(crate) struct Keyed(
+    pub struct Keyed(
         #[strategy(btree_map("[^\"]*", any::<TypeName>(), DEFAULT_SIZE_RANGE))]
-        pub(crate)  Map<String, TypeName>,
+        pub  Map<String, TypeName>,
     );
 
+    /// Member types by discriminant, which is under one key of a map and the member's value under another
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     #[derive(test_strategy::Arbitrary)]
-    pub(crate) struct Envelope {
+    pub struct Envelope {
+        /// Key of the discriminant
         #[strategy("[^\"]*")]
-        pub(crate) discriminant_key: String,
+        pub discriminant_key: String,
 
+        /// Key of the member's value
         #[strategy("[^\"]*")]
-        pub(crate) content_key: String,
+        pub content_key: String,
 
+        /// Member types by discriminant
         #[strategy(btree_map("[^\"]*", any::<TypeName>(), DEFAULT_SIZE_RANGE))]
-        pub(crate) discriminant_table: Map<String, TypeName>,
+        pub discriminant_table: Map<String, TypeName>,
     }
 
+    /// Member types by discriminant, which is added to the member's map
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     #[derive(test_strategy::Arbitrary)]
-    pub(crate) struct Inline {
+    pub struct Inline {
+        /// Key of the discriminant
         #[strategy("[^\"]*")]
-        pub(crate) discriminant_key: String,
+        pub discriminant_key: String,
 
+        /// Member types by discriminant
         #[strategy(btree_map("[^\"]*", any::<TypeName>(), DEFAULT_SIZE_RANGE))]
-        pub(crate) discriminant_table: Map<String, TypeName>,
+        pub discriminant_table: Map<String, TypeName>,
     }
 
+    /// Member types by the byte prefixed to their bytes
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     #[derive(test_strategy::Arbitrary)]
-    pub(crate) struct BytePrefix {
+    pub struct BytePrefix {
+        /// Discriminant byte of each member type
         #[strategy(btree_map(any::<TypeName>(), any::<u8>(), DEFAULT_SIZE_RANGE))]
-        pub(crate) discriminant_table: Map<TypeName, u8>,
+        pub discriminant_table: Map<TypeName, u8>,
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// A struct type with named fields
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[derive(test_strategy::Arbitrary)]
-pub(crate) struct TypeStruct {
+pub struct TypeStruct {
     // TODO: increase size range
     #[strategy(btree_map(any::<FieldName>(), any::<StructField>(), DEFAULT_SIZE_RANGE))]
     fields: Map<FieldName, StructField>,
     representation: StructRepresentation,
 }
 
+impl TypeStruct {
+    /// Creates a struct type with no fields and the default representation
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Iterates over the struct's fields, ordered by name
+    pub fn fields(&self) -> std::collections::btree_map::Iter<'_, FieldName, StructField> {
+        self.fields.iter()
+    }
+
+    /// Looks up the field named `name`
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<&StructField> {
+        self.fields.get(name)
+    }
+
+    /// How values are represented in the Data Model
+    #[must_use]
+    pub fn representation(&self) -> &StructRepresentation {
+        &self.representation
+    }
+}
+
+/// Name of a struct field
 #[derive(
     Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, test_strategy::Arbitrary,
 )]
-pub(crate) struct FieldName(#[strategy("[a-zA-Z0-9_]+")] String);
+pub struct FieldName(#[strategy("[a-zA-Z0-9_]+")] String);
+
+impl FieldName {
+    /// Name as written in the schema
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for FieldName {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        schema_dsl::field_name(s)
+    }
+}
+
+impl Borrow<str> for FieldName {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
 
+/// Type and modifiers of a struct field
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[derive(test_strategy::Arbitrary)]
-struct StructField {
+pub struct StructField {
     r#type: TypeTerm,
 
     #[serde(default, skip_serializing_if = "is_default")]
@@ -361,95 +793,180 @@ struct StructField {
     nullable: bool,
 }
 
+impl StructField {
+    /// Creates a field of the given type and modifiers
+    pub fn new(r#type: impl Into<TypeTerm>, optional: bool, nullable: bool) -> Self {
+        Self {
+            r#type: r#type.into(),
+            optional,
+            nullable,
+        }
+    }
+
+    /// Type of the field's values
+    #[must_use]
+    pub fn type_term(&self) -> &TypeTerm {
+        &self.r#type
+    }
+
+    /// Whether the field may be absent
+    #[must_use]
+    pub fn optional(&self) -> bool {
+        self.optional
+    }
+
+    /// Whether the field may be null
+    #[must_use]
+    pub fn nullable(&self) -> bool {
+        self.nullable
+    }
+}
+
+/// Reference to a named type or an anonymous map or list type defined inline
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(untagged)]
 #[derive(test_strategy::Arbitrary)]
 // TODO: allow all variants; may require proptest's prop_recursive strategy
-pub(crate) enum TypeTerm {
+pub enum TypeTerm {
+    /// Reference to a named type
     TypeName(TypeName),
+    /// Map or list type defined inline
     #[weight(0)]
     InlineDefn(Box<InlineDefn>),
 }
 
+impl TypeTerm {
+    /// The referenced type's name, unless the type is defined inline
+    #[must_use]
+    pub fn type_name(&self) -> Option<&TypeName> {
+        match self {
+            Self::TypeName(name) => Some(name),
+            Self::InlineDefn(_) => None,
+        }
+    }
+}
+
+impl From<TypeName> for TypeTerm {
+    fn from(name: TypeName) -> Self {
+        Self::TypeName(name)
+    }
+}
+
+impl From<TypeMap> for TypeTerm {
+    fn from(map: TypeMap) -> Self {
+        Self::InlineDefn(Box::new(InlineDefn::Map(map)))
+    }
+}
+
+impl From<TypeList> for TypeTerm {
+    fn from(list: TypeList) -> Self {
+        Self::InlineDefn(Box::new(InlineDefn::List(list)))
+    }
+}
+
+/// Anonymous map or list type defined inline
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(tag = "kind", rename_all = "lowercase")]
 #[derive(test_strategy::Arbitrary)]
-pub(crate) enum InlineDefn {
+pub enum InlineDefn {
+    /// Inline map type, e.g. `{String:Int}`
     Map(TypeMap),
+    /// Inline list type, e.g. `[Int]`
     List(TypeList),
 }
 
+/// How a struct type is represented in the Data Model
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "lowercase")]
 #[derive(test_strategy::Arbitrary)]
 // TODO: generate all variants
 // TODO: all FieldNames generated here should correspond to one of TypeStruct's fields
-enum StructRepresentation {
+pub enum StructRepresentation {
+    /// As a map from field names to values, the default
     Map(struct_representation::Map),
+    /// As a list of field values
     Tuple(struct_representation::Tuple),
+    /// As a string of delimited field name and value pairs
     StringPairs(struct_representation::StringPairs),
 
     // can't handle this variant until field order matches set of fields
+    /// As a string of field values joined by a separator
     #[weight(0)]
     StringJoin(struct_representation::StringJoin),
+    /// As a list of field name and value pairs
     ListPairs(struct_representation::ListPairs),
 }
 
-mod struct_representation {
+/// Details of each [`StructRepresentation`]
+pub mod struct_representation {
     use super::{AnyScalar, FieldName};
     use serde::{Deserialize, Serialize};
 
     use super::DEFAULT_SIZE_RANGE;
     use proptest::{collection::btree_map, prelude::any};
 
+    /// Representation as a map
     #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
     #[serde(rename_all = "camelCase")]
     #[derive(test_strategy::Arbitrary)]
-    pub(crate) struct Map {
+    pub struct Map {
+        /// Renames and implicit values of fields, by name
         #[serde(default)]
         #[strategy(btree_map(any::<FieldName>(), any::<MapFieldDetails>(), DEFAULT_SIZE_RANGE))]
-        pub(crate) fields: super::Map<FieldName, MapFieldDetails>,
+        pub fields: super::Map<FieldName, MapFieldDetails>,
     }
 
+    /// How a field is represented in a map
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     #[derive(test_strategy::Arbitrary)]
-    pub(crate) struct MapFieldDetails {
-        pub(crate) rename: Option<String>,
-        pub(crate) implicit: Option<AnyScalar>,
+    pub struct MapFieldDetails {
+        /// Key the field's value is under instead of its name
+        pub rename: Option<String>,
+        /// Value the field has when its key is absent
+        pub implicit: Option<AnyScalar>,
     }
 
+    /// Representation as a list of field values
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     #[derive(test_strategy::Arbitrary)]
-    pub(crate) struct Tuple {
+    pub struct Tuple {
+        /// Order of the fields in the list, or that of the struct's fields if absent
         // TODO: remove Option
-        pub(crate) field_order: Option<Vec<FieldName>>,
+        pub field_order: Option<Vec<FieldName>>,
     }
 
+    /// Representation as a string of field name and value pairs, e.g. `x=1,y=2`
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     #[derive(test_strategy::Arbitrary)]
-    pub(crate) struct StringPairs {
+    pub struct StringPairs {
+        /// Separator between a field's name and its value
         #[strategy("[^\"]+")]
-        pub(crate) inner_delim: String,
+        pub inner_delim: String,
 
+        /// Separator between fields
         #[strategy("[^\"]+")]
-        pub(crate) entry_delim: String,
+        pub entry_delim: String,
     }
 
+    /// Representation as a string of field values joined by a separator, e.g. `1:2`
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
     #[serde(rename_all = "camelCase")]
     #[derive(test_strategy::Arbitrary)]
-    pub(crate) struct StringJoin {
+    pub struct StringJoin {
+        /// Separator between field values
         #[strategy("[^\"]+")]
-        pub(crate) join: String,
+        pub join: String,
 
-        pub(crate) field_order: Vec<FieldName>,
+        /// Order of the fields in the string
+        pub field_order: Vec<FieldName>,
     }
 
+    /// Representation as a list of two-item lists, each a field name and its value
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-    pub(crate) struct ListPairs;
+    pub struct ListPairs;
 }
 
 impl Default for StructRepresentation {
@@ -458,42 +975,93 @@ impl Default for StructRepresentation {
     }
 }
 
+/// An enum type with named members
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-pub(crate) struct TypeEnum {
+pub struct TypeEnum {
     #[strategy(btree_map(any::<EnumValue>(), any::<Null>(), DEFAULT_SIZE_RANGE))]
     members: Map<EnumValue, Null>,
     representation: EnumRepresentation,
 }
 
+impl TypeEnum {
+    /// Creates an enum type with the default representation
+    pub fn new(members: impl IntoIterator<Item = EnumValue>) -> Self {
+        Self {
+            members: members.into_iter().map(|m| (m, Null)).collect(),
+            representation: EnumRepresentation::default(),
+        }
+    }
+
+    /// Iterates over the enum's members, ordered by name
+    pub fn members(&self) -> impl Iterator<Item = &EnumValue> {
+        self.members.keys()
+    }
+
+    /// How values are represented in the Data Model
+    #[must_use]
+    pub fn representation(&self) -> &EnumRepresentation {
+        &self.representation
+    }
+}
+
+/// Name of an enum member
 #[derive(
     Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, test_strategy::Arbitrary,
 )]
-pub(crate) struct EnumValue(#[strategy("[a-z0-9_]+")] String);
+pub struct EnumValue(#[strategy("[a-z0-9_]+")] String);
+
+impl EnumValue {
+    /// Name as written in the schema
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
 
+impl FromStr for EnumValue {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        schema_dsl::enum_value(s)
+    }
+}
+
+impl Borrow<str> for EnumValue {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+/// How an enum type is represented in the Data Model
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "lowercase")]
 #[derive(test_strategy::Arbitrary)]
-enum EnumRepresentation {
+pub enum EnumRepresentation {
+    /// As a string, the member's name unless the table gives another
     String(enum_representation::String),
+    /// As an integer given by the table
     #[weight(0)]
     Int(enum_representation::Int),
 }
 
-mod enum_representation {
+/// Details of each [`EnumRepresentation`]
+pub mod enum_representation {
     use super::{EnumValue, Map, DEFAULT_SIZE_RANGE};
     use proptest::{collection::btree_map, prelude::*};
     use serde::{Deserialize, Serialize};
 
+    /// Strings representing members by something other than their names
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, test_strategy::Arbitrary)]
-    pub(crate) struct String(
+    pub struct String(
         #[strategy(btree_map(any::<EnumValue>(), "[^\"]*", DEFAULT_SIZE_RANGE))]
-        Map<EnumValue, std::string::String>,
+        pub  Map<EnumValue, std::string::String>,
     );
 
+    /// Integer representing each member
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, test_strategy::Arbitrary)]
-    pub(crate) struct Int(
-        #[strategy(btree_map(any::<EnumValue>(), any::<Int>(), DEFAULT_SIZE_RANGE))]
-        Map<EnumValue, Int>,
+    pub struct Int(
+        #[strategy(btree_map(any::<EnumValue>(), any::<super::Int>(), DEFAULT_SIZE_RANGE))]
+        pub  Map<EnumValue, super::Int>,
     );
 }
 
@@ -503,11 +1071,26 @@ impl Default for EnumRepresentation {
     }
 }
 
+/// A copy of another type, e.g. `= Foo`
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, test_strategy::Arbitrary)]
-pub(crate) struct TypeCopy {
+pub struct TypeCopy {
     from_type: TypeName,
 }
 
+impl TypeCopy {
+    /// Creates a copy of `from_type`
+    #[must_use]
+    pub fn new(from_type: TypeName) -> Self {
+        Self { from_type }
+    }
+
+    /// Name of the type being copied
+    #[must_use]
+    pub fn from_type(&self) -> &TypeName {
+        &self.from_type
+    }
+}
+
 const L_BOOL: &str = "bool";
 const L_STRING: &str = "string";
 const L_BYTES: &str = "bytes";
@@ -620,9 +1203,16 @@ impl fmt::Display for AnyScalar {
         match self {
             Self::Bool(x) => write!(f, "\"{}\"", x),
             Self::String(x) => write!(f, "\"{}\"", x),
-            Self::Bytes(_x) => todo!("literal bytes"), // write!(f, "{}", x),
+            Self::Bytes(x) => {
+                write!(f, "x\"")?;
+                for byte in x {
+                    write!(f, "{:02x}", byte)?;
+                }
+                write!(f, "\"")
+            }
             Self::Int(x) => write!(f, "{}", x),
-            Self::Float(x) => write!(f, "{}", x),
+            // unlike `Display`, `Debug` always includes a decimal point or exponent, distinguishing it from an int
+            Self::Float(x) => write!(f, "{:?}", x),
         }
     }
 }
@@ -1091,16 +1681,17 @@ peg::parser! {
         rule as_bool_false() -> AnyScalar = "\"false\"" { AnyScalar::Bool(false) }
         rule as_bool_true() -> AnyScalar = "\"true\"" { AnyScalar::Bool(true) }
         rule as_string() -> AnyScalar = s:string() { AnyScalar::String(s) }
-        rule as_bytes() -> AnyScalar = "x" { todo!() }
-        rule as_int() -> AnyScalar = "x" { todo!() }
-        rule as_float() -> AnyScalar = "x" { todo!() }
+        rule as_bytes() -> AnyScalar = "x\"" hs:$((['0'..='9' | 'a'..='f' | 'A'..='F'])*) "\"" {? decode_hex(hs).map(AnyScalar::Bytes).ok_or("an even number of hex digits") }
+        rule as_int() -> AnyScalar = s:$("-"? ['0'..='9']+) {? s.parse().map(AnyScalar::Int).or(Err("an integer")) }
+        rule _exponent() -> () = ['e' | 'E'] ['+' | '-']? ['0'..='9']+ { }
+        rule as_float() -> AnyScalar = s:$("-"? ['0'..='9']+ (("." ['0'..='9']+ _exponent()?) / _exponent())) {? s.parse().map(AnyScalar::Float).or(Err("a float")) }
         rule any_scalar() -> AnyScalar = a:(
             as_bool_false() /
             as_bool_true() /
             as_string() /
             as_bytes() /
-            as_int() /
-            as_float()
+            as_float() /
+            as_int()
         ) { a }
 
 
@@ -1275,6 +1866,92 @@ mod tests {
         schema_roundtrips_through_dsl(&schema_schema());
     }
 
+    #[test]
+    fn parsed_types_are_accessible() {
+        let schema: Schema = "type Foo struct {\n  bar optional nullable String\n} representation tuple\n\ntype Baz nullable {String:Foo}\n"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            schema
+                .types()
+                .names()
+                .map(TypeName::as_str)
+                .collect::<Vec<_>>(),
+            ["Baz", "Foo"]
+        );
+        assert!(schema.get("Missing").is_none());
+
+        match schema.get("Foo") {
+            Some(Type::Struct(s)) => {
+                let bar = s.field("bar").unwrap();
+                assert!(bar.optional());
+                assert!(bar.nullable());
+                assert_eq!(
+                    bar.type_term().type_name().map(TypeName::as_str),
+                    Some("String")
+                );
+                assert!(matches!(s.representation(), StructRepresentation::Tuple(_)));
+            }
+            other => panic!("unexpected type: {:?}", other),
+        }
+
+        match schema.get("Baz") {
+            Some(Type::Map(m)) => {
+                assert_eq!(m.key_type().as_str(), "String");
+                assert!(m.value_nullable());
+                assert!(matches!(m.representation(), MapRepresentation::Map(_)));
+            }
+            other => panic!("unexpected type: {:?}", other),
+        }
+
+        assert_eq!(Schema::from_dsl(&schema.to_dsl()).unwrap(), schema);
+        assert_eq!(
+            Schema::from_json(&serde_json::to_string(&schema).unwrap()).unwrap(),
+            schema
+        );
+    }
+
+    #[test]
+    fn implicit_literals_of_every_kind_roundtrip() {
+        let dsl = "type Foo struct {\n  a Bool (implicit \"true\")\n  b String (implicit \"hi\")\n  c Bytes (implicit x\"00ff\")\n  d Int (implicit -12)\n  e Float (implicit 1.0)\n  f Float (implicit 2.5e-7)\n}\n";
+        let schema: Schema = dsl.parse().unwrap();
+
+        let implicits: Vec<_> = match schema.get("Foo") {
+            Some(Type::Struct(TypeStruct {
+                representation: StructRepresentation::Map(struct_representation::Map { fields }),
+                ..
+            })) => fields
+                .values()
+                .map(|d| d.implicit.clone().unwrap())
+                .collect(),
+            _ => panic!("expected a map-represented struct"),
+        };
+        assert_eq!(
+            implicits,
+            [
+                AnyScalar::Bool(true),
+                AnyScalar::String("hi".to_string()),
+                AnyScalar::Bytes(vec![0x00, 0xff]),
+                AnyScalar::Int(-12),
+                AnyScalar::Float(1.0),
+                AnyScalar::Float(2.5e-7),
+            ]
+        );
+        assert_eq!(schema.to_dsl().parse::<Schema>().unwrap(), schema);
+        assert!("type Foo struct {\n  c Bytes (implicit x\"0\")\n}\n"
+            .parse::<Schema>()
+            .is_err());
+    }
+
+    #[test]
+    fn names_are_checked_when_parsed() {
+        assert!("Foo".parse::<TypeName>().is_ok());
+        assert!("foo".parse::<TypeName>().is_err());
+        assert!("foo bar".parse::<FieldName>().is_err());
+        assert!("foo-bar".parse::<EnumValue>().is_err());
+    }
+
     #[proptest(cases = CASES, max_shrink_iters = MAX_SHRINK_ITERS)]
     fn roundtrips_through_dsl_form(schema: Schema) {
         schema_roundtrips_through_dsl(&schema);
@@ -0,0 +1,496 @@
+use std::fmt;
+
+use super::{
+    BytesRepresentation, EnumRepresentation, FieldName, ListRepresentation, MapRepresentation,
+    Reference, Schema, StructField, StructRepresentation, Type, TypeName, UnionRepresentation,
+    PRELUDE,
+};
+
+/// Representation of any kind of type, for use with [`SchemaBuilder::set_representation`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Representation {
+    /// Representation of a bytes type
+    Bytes(BytesRepresentation),
+    /// Representation of a map type
+    Map(MapRepresentation),
+    /// Representation of a list type
+    List(ListRepresentation),
+    /// Representation of a union type, including its members
+    Union(UnionRepresentation),
+    /// Representation of a struct type
+    Struct(StructRepresentation),
+    /// Representation of an enum type
+    Enum(EnumRepresentation),
+}
+
+impl Representation {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Bytes(_) => super::L_BYTES,
+            Self::Map(_) => super::L_MAP,
+            Self::List(_) => super::L_LIST,
+            Self::Union(_) => super::L_UNION,
+            Self::Struct(_) => super::L_STRUCT,
+            Self::Enum(_) => super::L_ENUM,
+        }
+    }
+}
+
+impl From<BytesRepresentation> for Representation {
+    fn from(repr: BytesRepresentation) -> Self {
+        Self::Bytes(repr)
+    }
+}
+
+impl From<MapRepresentation> for Representation {
+    fn from(repr: MapRepresentation) -> Self {
+        Self::Map(repr)
+    }
+}
+
+impl From<ListRepresentation> for Representation {
+    fn from(repr: ListRepresentation) -> Self {
+        Self::List(repr)
+    }
+}
+
+impl From<UnionRepresentation> for Representation {
+    fn from(repr: UnionRepresentation) -> Self {
+        Self::Union(repr)
+    }
+}
+
+impl From<StructRepresentation> for Representation {
+    fn from(repr: StructRepresentation) -> Self {
+        Self::Struct(repr)
+    }
+}
+
+impl From<EnumRepresentation> for Representation {
+    fn from(repr: EnumRepresentation) -> Self {
+        Self::Enum(repr)
+    }
+}
+
+/// Reasons a [`SchemaBuilder`] refuses an edit
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditError {
+    /// A type with the name is already declared
+    AlreadyDeclared(TypeName),
+    /// No type with the name is declared
+    Undeclared(String),
+    /// The name is that of a prelude type, which a type added or renamed with it would shadow
+    PreludeName(TypeName),
+    /// Fields can only be added to structs
+    NotAStruct(TypeName),
+    /// The struct already has a field with the name
+    FieldAlreadyDeclared {
+        /// Name of the struct
+        type_name: TypeName,
+        /// Name of the field
+        field: FieldName,
+    },
+    /// The representation is for a different kind of type
+    RepresentationMismatch {
+        /// Name of the type
+        type_name: TypeName,
+        /// Kind of type the representation is for, e.g. `union`
+        representation: &'static str,
+    },
+    /// Removing the type would leave these references to it dangling
+    StillReferenced(Vec<Reference>),
+    /// These references name types which aren't declared
+    DanglingReferences(Vec<Reference>),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::AlreadyDeclared(name) => write!(f, "type `{}` is already declared", name),
+            Self::Undeclared(name) => write!(f, "type `{}` isn't declared", name),
+            Self::PreludeName(name) => write!(f, "`{}` is the name of a prelude type", name),
+            Self::NotAStruct(name) => write!(f, "type `{}` isn't a struct", name),
+            Self::FieldAlreadyDeclared { type_name, field } => write!(
+                f,
+                "struct `{}` already has a field named `{}`",
+                type_name, field
+            ),
+            Self::RepresentationMismatch {
+                type_name,
+                representation,
+            } => write!(
+                f,
+                "type `{}` can't have a {} representation",
+                type_name, representation
+            ),
+            Self::StillReferenced(refs) => {
+                write!(f, "type is still referenced from")?;
+                write_references(f, refs)
+            }
+            Self::DanglingReferences(refs) => {
+                write!(f, "undeclared types are referenced from")?;
+                write_references(f, refs)
+            }
+        }
+    }
+}
+
+fn write_references(f: &mut fmt::Formatter<'_>, refs: &[Reference]) -> Result<(), fmt::Error> {
+    for (i, r) in refs.iter().enumerate() {
+        let sep = if i == 0 { " " } else { ", " };
+        write!(f, "{}`{}` (to `{}`)", sep, r.location, r.target)?;
+    }
+    Ok(())
+}
+
+impl std::error::Error for EditError {}
+
+/// Builds or edits a [`Schema`] programmatically
+///
+/// Types may refer to types which haven't been added yet, so mutually recursive types can be built one at a time.
+/// [`SchemaBuilder::build`] refuses to finish while any references are dangling, and edits that would leave
+/// references dangling are refused.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchemaBuilder {
+    schema: Schema,
+}
+
+impl SchemaBuilder {
+    /// Creates a builder for a schema with no types
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a new type
+    ///
+    /// # Errors
+    ///
+    /// Fails if a type named `name` is already declared or if `name` is in the [`PRELUDE`].
+    pub fn add_type(
+        &mut self,
+        name: TypeName,
+        ty: impl Into<Type>,
+    ) -> Result<&mut Self, EditError> {
+        if self.schema.types.0.contains_key(&name) {
+            return Err(EditError::AlreadyDeclared(name));
+        }
+        if PRELUDE.contains(&name.as_str()) {
+            return Err(EditError::PreludeName(name));
+        }
+        self.schema.types.0.insert(name, ty.into());
+        Ok(self)
+    }
+
+    /// Adds a field to a struct type
+    ///
+    /// # Errors
+    ///
+    /// Fails if `type_name` isn't a declared struct or if it already has a field called `name`.
+    pub fn add_field(
+        &mut self,
+        type_name: &str,
+        name: FieldName,
+        field: StructField,
+    ) -> Result<&mut Self, EditError> {
+        let (type_name, ty) = self.get_mut(type_name)?;
+        match ty {
+            Type::Struct(s) => {
+                if s.fields.contains_key(&name) {
+                    return Err(EditError::FieldAlreadyDeclared {
+                        type_name,
+                        field: name,
+                    });
+                }
+                s.fields.insert(name, field);
+            }
+            _ => return Err(EditError::NotAStruct(type_name)),
+        }
+        Ok(self)
+    }
+
+    /// Replaces the representation of a type
+    ///
+    /// # Errors
+    ///
+    /// Fails if `type_name` isn't declared or if `representation` is for a different kind of type.
+    pub fn set_representation(
+        &mut self,
+        type_name: &str,
+        representation: impl Into<Representation>,
+    ) -> Result<&mut Self, EditError> {
+        let (type_name, ty) = self.get_mut(type_name)?;
+        match (ty, representation.into()) {
+            (Type::Bytes(b), Representation::Bytes(r)) => b.representation = r,
+            (Type::Map(m), Representation::Map(r)) => m.representation = r,
+            (Type::List(l), Representation::List(r)) => l.representation = r,
+            (Type::Union(u), Representation::Union(r)) => u.representation = r,
+            (Type::Struct(s), Representation::Struct(r)) => s.representation = r,
+            (Type::Enum(e), Representation::Enum(r)) => e.representation = r,
+            (_, r) => {
+                return Err(EditError::RepresentationMismatch {
+                    type_name,
+                    representation: r.kind(),
+                })
+            }
+        }
+        Ok(self)
+    }
+
+    /// Removes a type, returning its definition
+    ///
+    /// # Errors
+    ///
+    /// Fails if `name` isn't declared or if any other type refers to it.
+    pub fn remove_type(&mut self, name: &str) -> Result<Type, EditError> {
+        let (name, ty) = self
+            .schema
+            .types
+            .0
+            .remove_entry(name)
+            .ok_or_else(|| EditError::Undeclared(name.to_string()))?;
+
+        let refs: Vec<_> = self
+            .schema
+            .references()
+            .into_iter()
+            .filter(|r| r.target == name)
+            .collect();
+        if refs.is_empty() {
+            Ok(ty)
+        } else {
+            self.schema.types.0.insert(name, ty);
+            Err(EditError::StillReferenced(refs))
+        }
+    }
+
+    /// Renames a type along with every reference to it
+    ///
+    /// # Errors
+    ///
+    /// Fails if `from` isn't declared, if a type named `to` already is, or if `to` is in the [`PRELUDE`].
+    pub fn rename_type(&mut self, from: &str, to: TypeName) -> Result<&mut Self, EditError> {
+        if self.schema.types.0.contains_key(&to) {
+            return Err(EditError::AlreadyDeclared(to));
+        }
+        if PRELUDE.contains(&to.as_str()) {
+            return Err(EditError::PreludeName(to));
+        }
+        let ty = self
+            .schema
+            .types
+            .0
+            .remove(from)
+            .ok_or_else(|| EditError::Undeclared(from.to_string()))?;
+
+        self.schema.types.0.insert(to.clone(), ty);
+        for ty in self.schema.types.0.values_mut() {
+            ty.rename_references(from, &to);
+        }
+        Ok(self)
+    }
+
+    /// Lists references to types which are neither declared nor in the [`PRELUDE`](super::PRELUDE)
+    #[must_use]
+    pub fn dangling_references(&self) -> Vec<Reference> {
+        self.schema
+            .references()
+            .into_iter()
+            .filter(|r| !self.schema.resolves(r.target.as_str()))
+            .collect()
+    }
+
+    /// Finishes building the schema
+    ///
+    /// # Errors
+    ///
+    /// Fails if any references are dangling.
+    pub fn build(self) -> Result<Schema, EditError> {
+        let dangling = self.dangling_references();
+        if dangling.is_empty() {
+            Ok(self.schema)
+        } else {
+            Err(EditError::DanglingReferences(dangling))
+        }
+    }
+
+    fn get_mut(&mut self, name: &str) -> Result<(TypeName, &mut Type), EditError> {
+        self.schema
+            .types
+            .0
+            .iter_mut()
+            .find(|(n, _)| n.as_str() == name)
+            .map(|(n, ty)| (n.clone(), ty))
+            .ok_or_else(|| EditError::Undeclared(name.to_string()))
+    }
+}
+
+impl From<Schema> for SchemaBuilder {
+    fn from(schema: Schema) -> Self {
+        Self { schema }
+    }
+}
+
+impl fmt::Display for SchemaBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::schema::{struct_representation, TypeList, TypeString, TypeStruct};
+
+    use pretty_assertions::assert_eq;
+
+    fn name(s: &str) -> TypeName {
+        s.parse().unwrap()
+    }
+
+    fn field(s: &str) -> FieldName {
+        s.parse().unwrap()
+    }
+
+    fn example() -> SchemaBuilder {
+        let mut builder = SchemaBuilder::new();
+        builder
+            .add_type(name("Names"), TypeList::new(name("Name"), false))
+            .unwrap()
+            .add_type(name("Name"), TypeString)
+            .unwrap()
+            .add_type(name("Person"), TypeStruct::new())
+            .unwrap()
+            .add_field(
+                "Person",
+                field("names"),
+                StructField::new(name("Names"), false, false),
+            )
+            .unwrap()
+            .add_field(
+                "Person",
+                field("friend"),
+                StructField::new(name("Person"), true, false),
+            )
+            .unwrap()
+            .set_representation(
+                "Person",
+                StructRepresentation::Tuple(struct_representation::Tuple { field_order: None }),
+            )
+            .unwrap();
+        builder
+    }
+
+    #[test]
+    fn built_schema_renders_and_reparses() {
+        let schema = example().build().unwrap();
+
+        assert_eq!(
+            schema.to_dsl(),
+            "type Name string\n\ntype Names [Name]\n\ntype Person struct {\n  friend optional Person\n  names Names\n} representation tuple\n\n"
+        );
+        assert_eq!(Schema::from_dsl(&schema.to_dsl()).unwrap(), schema);
+    }
+
+    #[test]
+    fn edits_are_refused_when_they_dont_fit() {
+        let mut builder = example();
+
+        assert_eq!(
+            builder.add_type(name("Name"), TypeString).unwrap_err(),
+            EditError::AlreadyDeclared(name("Name"))
+        );
+        assert_eq!(
+            builder
+                .add_field(
+                    "Name",
+                    field("x"),
+                    StructField::new(name("Name"), false, false)
+                )
+                .unwrap_err(),
+            EditError::NotAStruct(name("Name"))
+        );
+        assert!(matches!(
+            builder.set_representation("Names", MapRepresentation::default()),
+            Err(EditError::RepresentationMismatch { .. })
+        ));
+        assert_eq!(
+            builder.remove_type("Nope").unwrap_err(),
+            EditError::Undeclared("Nope".to_string())
+        );
+    }
+
+    #[test]
+    fn referenced_types_cant_be_removed() {
+        let mut builder = example();
+
+        match builder.remove_type("Name").unwrap_err() {
+            EditError::StillReferenced(refs) => {
+                assert_eq!(refs.len(), 1);
+                assert_eq!(refs[0].location.to_string(), "Names[value]");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        // only referenced by itself
+        builder.remove_type("Person").unwrap();
+    }
+
+    #[test]
+    fn renaming_updates_references() {
+        let mut builder = example();
+        builder.rename_type("Name", name("Nickname")).unwrap();
+
+        let schema = builder.build().unwrap();
+        assert!(schema.get("Name").is_none());
+        assert!(schema
+            .references()
+            .iter()
+            .any(|r| r.target.as_str() == "Nickname"));
+    }
+
+    #[test]
+    fn types_cant_be_renamed_to_prelude_types() {
+        let mut builder = example();
+
+        for prelude in &["String", "Any"] {
+            assert_eq!(
+                builder.rename_type("Name", name(prelude)).unwrap_err(),
+                EditError::PreludeName(name(prelude))
+            );
+        }
+        assert!(builder.build().unwrap().get("Name").is_some());
+    }
+
+    #[test]
+    fn types_cant_be_added_with_prelude_names() {
+        let mut builder = SchemaBuilder::new();
+
+        for prelude in &["String", "Any"] {
+            assert_eq!(
+                builder.add_type(name(prelude), TypeString).unwrap_err(),
+                EditError::PreludeName(name(prelude))
+            );
+        }
+        assert_eq!(builder, SchemaBuilder::new());
+    }
+
+    #[test]
+    fn dangling_references_prevent_building() {
+        let mut builder = SchemaBuilder::new();
+        builder
+            .add_type(name("Names"), TypeList::new(name("Name"), false))
+            .unwrap()
+            .add_type(name("Strings"), TypeList::new(name("String"), false))
+            .unwrap();
+
+        match builder.build().unwrap_err() {
+            EditError::DanglingReferences(refs) => {
+                assert_eq!(refs.len(), 1);
+                assert_eq!(refs[0].target, name("Name"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,1244 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use super::{
+    enum_representation,
+    reference::union_members,
+    resolve::{describe_cycle, ANY_KIND},
+    struct_representation, union_representation, AnyScalar, BytesRepresentation,
+    EnumRepresentation, EnumValue, FieldName, InlineDefn, Int, Location, MapRepresentation,
+    Reference, RepresentationKind, Schema, Segment, StructRepresentation, Type, TypeEnum, TypeMap,
+    TypeName, TypeStruct, TypeTerm, TypeUnion, UnionRepresentation, PRELUDE,
+};
+
+/// Integers which every IPLD implementation can represent exactly, including those storing numbers as IEEE 754
+/// doubles
+pub const SAFE_INT_RANGE: std::ops::RangeInclusive<Int> = -((1 << 53) - 1)..=(1 << 53) - 1;
+
+/// A way in which a schema breaks the rules for well-formed schemas
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    /// A referenced type is neither declared nor in the [`PRELUDE`]
+    UndefinedType {
+        /// Where the name is used
+        location: Location,
+        /// The undefined name
+        name: TypeName,
+        /// A similarly named type which may have been intended
+        suggestion: Option<TypeName>,
+    },
+    /// Following copies from a type leads back to it, so the copies never reach an actual type
+    CopyCycle {
+        /// Where the first copy in the cycle is declared
+        location: Location,
+        /// Names of the copies in the cycle, in the order they're followed
+        cycle: Vec<TypeName>,
+    },
+    /// A map's key type isn't represented as a string
+    NonStringMapKey {
+        /// Where the key type is referenced
+        location: Location,
+        /// The map's key type
+        key_type: TypeName,
+        /// Kinds the key type is represented as instead
+        kinds: Vec<RepresentationKind>,
+    },
+    /// A struct's representation details name a field the struct doesn't declare
+    UndeclaredField {
+        /// Where the struct is declared
+        location: Location,
+        /// The field's name
+        field: FieldName,
+    },
+    /// A struct's `fieldOrder` doesn't list each of its declared fields exactly once
+    FieldOrderMismatch {
+        /// Where the struct is declared
+        location: Location,
+        /// Declared fields which aren't listed
+        missing: Vec<FieldName>,
+        /// Fields listed more than once
+        repeated: Vec<FieldName>,
+    },
+    /// A field of a struct represented as a string isn't itself represented as a string
+    NonStringField {
+        /// Where the field is declared
+        location: Location,
+        /// Name of the struct's representation strategy, e.g. `stringjoin`
+        representation: &'static str,
+        /// Kinds the field's type is represented as instead
+        kinds: Vec<RepresentationKind>,
+    },
+    /// A kinded union's member isn't represented as the kind it's listed under
+    KindMismatch {
+        /// Where the member is listed
+        location: Location,
+        /// The member's name
+        member: TypeName,
+        /// Kind the member is listed under
+        kind: RepresentationKind,
+        /// Kinds the member is represented as instead
+        kinds: Vec<RepresentationKind>,
+    },
+    /// An inline union's member isn't a struct represented as a map
+    InlineMemberNotMapStruct {
+        /// Where the member is listed
+        location: Location,
+        /// The member's name
+        member: TypeName,
+    },
+    /// An inline union's member has a field serialized under the union's discriminant key
+    DiscriminantKeyCollision {
+        /// Where the member is listed
+        location: Location,
+        /// The member's name
+        member: TypeName,
+        /// The field serialized under the key
+        field: FieldName,
+        /// The union's discriminant key
+        discriminant_key: String,
+    },
+    /// An envelope union uses the same key for its discriminant and content
+    EnvelopeKeysCollide {
+        /// Where the union is declared
+        location: Location,
+        /// The key used for both
+        key: String,
+    },
+    /// A byteprefix union uses the same byte to identify more than one member
+    DuplicateBytePrefix {
+        /// Where the union is declared
+        location: Location,
+        /// The shared byte
+        byte: u8,
+        /// Members the byte identifies
+        members: Vec<TypeName>,
+    },
+    /// A byteprefix union's member isn't represented as bytes, so it can't hold the bytes after the prefix
+    BytePrefixMemberNotBytes {
+        /// Where the member is listed
+        location: Location,
+        /// The member's name
+        member: TypeName,
+        /// Kinds the member is represented as instead
+        kinds: Vec<RepresentationKind>,
+    },
+    /// A struct field has an implicit value but is also optional, leaving its absence ambiguous
+    ImplicitOptional {
+        /// Where the field is declared
+        location: Location,
+    },
+    /// A struct field's implicit value isn't a value of the field's type
+    ImplicitMismatch {
+        /// Where the field is declared
+        location: Location,
+        /// The implicit value
+        implicit: AnyScalar,
+        /// The field's type
+        type_term: TypeTerm,
+    },
+    /// An enum's representation gives a value for something which isn't one of its members
+    UndeclaredEnumMember {
+        /// Where the enum is declared
+        location: Location,
+        /// The name given a value
+        member: EnumValue,
+    },
+    /// An enum represents more than one member with the same value
+    DuplicateEnumRepresentation {
+        /// Where the enum is declared
+        location: Location,
+        /// The shared value, as it appears in the DSL
+        value: String,
+        /// Members represented by the value
+        members: Vec<EnumValue>,
+    },
+    /// An int-represented enum has no integer for one of its members
+    MissingEnumInt {
+        /// Where the enum is declared
+        location: Location,
+        /// The member without an integer
+        member: EnumValue,
+    },
+    /// An int-represented enum uses an integer outside [`SAFE_INT_RANGE`] for a member
+    EnumIntOutOfRange {
+        /// Where the enum is declared
+        location: Location,
+        /// The member given the integer
+        member: EnumValue,
+        /// The integer
+        value: Int,
+    },
+    /// A type has no finite values, e.g. a struct with a required field of its own type
+    Uninhabited {
+        /// Where the type is declared
+        location: Location,
+        /// References to types which would be needed to construct a value but have none either; empty when the
+        /// type has no members
+        because: Vec<Reference>,
+    },
+}
+
+impl fmt::Display for Problem {
+    #[allow(clippy::too_many_lines)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::UndefinedType {
+                location,
+                name,
+                suggestion,
+            } => {
+                write!(f, "undefined type `{}` referenced at `{}`", name, location)?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, "; did you mean `{}`?", suggestion)?;
+                }
+                Ok(())
+            }
+            Self::CopyCycle { location, cycle } => write!(
+                f,
+                "copy `{}` never reaches a type because copies form a cycle: {}",
+                location,
+                describe_cycle(cycle)
+            ),
+            Self::NonStringMapKey {
+                location,
+                key_type,
+                kinds,
+            } => write!(
+                f,
+                "map key type `{}` at `{}` is represented as {} but map keys must be represented as string",
+                key_type,
+                location,
+                describe_kinds(kinds)
+            ),
+            Self::UndeclaredField { location, field } => write!(
+                f,
+                "representation of `{}` refers to undeclared field `{}`",
+                location, field
+            ),
+            Self::FieldOrderMismatch {
+                location,
+                missing,
+                repeated,
+            } => {
+                write!(
+                    f,
+                    "field order of `{}` must list each declared field exactly once",
+                    location
+                )?;
+                if !missing.is_empty() {
+                    write!(f, "; missing {}", describe_fields(missing))?;
+                }
+                if !repeated.is_empty() {
+                    write!(f, "; repeated {}", describe_fields(repeated))?;
+                }
+                Ok(())
+            }
+            Self::NonStringField {
+                location,
+                representation,
+                kinds,
+            } => write!(
+                f,
+                "field `{}` is represented as {} but fields of {} structs must be represented as string",
+                location,
+                describe_kinds(kinds),
+                representation
+            ),
+            Self::KindMismatch {
+                location,
+                member,
+                kind,
+                kinds,
+            } => write!(
+                f,
+                "member `{}` at `{}` is represented as {} but is listed as {}",
+                member,
+                location,
+                describe_kinds(kinds),
+                kind
+            ),
+            Self::InlineMemberNotMapStruct { location, member } => write!(
+                f,
+                "member `{}` at `{}` of an inline union must be a struct represented as a map",
+                member, location
+            ),
+            Self::DiscriminantKeyCollision {
+                location,
+                member,
+                field,
+                discriminant_key,
+            } => write!(
+                f,
+                "member `{}` at `{}` has field `{}` serialized under the union's discriminant key {:?}",
+                member, location, field, discriminant_key
+            ),
+            Self::EnvelopeKeysCollide { location, key } => write!(
+                f,
+                "envelope union `{}` uses {:?} as both its discriminant key and content key",
+                location, key
+            ),
+            Self::DuplicateBytePrefix {
+                location,
+                byte,
+                members,
+            } => write!(
+                f,
+                "byteprefix union `{}` uses byte {} for more than one member: {}",
+                location,
+                byte,
+                members
+                    .iter()
+                    .map(|member| format!("`{}`", member))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::BytePrefixMemberNotBytes {
+                location,
+                member,
+                kinds,
+            } => write!(
+                f,
+                "member `{}` at `{}` of a byteprefix union is represented as {} but must be bytes",
+                member,
+                location,
+                describe_kinds(kinds)
+            ),
+            Self::ImplicitOptional { location } => write!(
+                f,
+                "field `{}` can't both be optional and have an implicit value",
+                location
+            ),
+            Self::ImplicitMismatch {
+                location,
+                implicit,
+                type_term,
+            } => write!(
+                f,
+                "implicit value {} of field `{}` isn't a value of its type `{}`",
+                implicit, location, type_term
+            ),
+            Self::UndeclaredEnumMember { location, member } => write!(
+                f,
+                "representation of `{}` refers to undeclared member `{}`",
+                location, member
+            ),
+            Self::DuplicateEnumRepresentation {
+                location,
+                value,
+                members,
+            } => write!(
+                f,
+                "enum `{}` represents members {} with the same value {}",
+                location,
+                members
+                    .iter()
+                    .map(|member| format!("`{}`", member))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                value
+            ),
+            Self::MissingEnumInt { location, member } => write!(
+                f,
+                "int-represented enum `{}` has no integer for member `{}`",
+                location, member
+            ),
+            Self::EnumIntOutOfRange {
+                location,
+                member,
+                value,
+            } => write!(
+                f,
+                "member `{}` of `{}` is represented as {}, outside the range of integers every IPLD implementation can represent ({} to {})",
+                member,
+                location,
+                value,
+                SAFE_INT_RANGE.start(),
+                SAFE_INT_RANGE.end()
+            ),
+            Self::Uninhabited { location, because } if because.is_empty() => write!(
+                f,
+                "type `{}` can never have a value because it has no members",
+                location
+            ),
+            Self::Uninhabited { location, because } => write!(
+                f,
+                "type `{}` can never have a value because {} can't have one either",
+                location,
+                because
+                    .iter()
+                    .map(|reference| format!("`{}` at `{}`", reference.target, reference.location))
+                    .collect::<Vec<_>>()
+                    .join(" and ")
+            ),
+        }
+    }
+}
+
+fn describe_fields(fields: &[FieldName]) -> String {
+    fields
+        .iter()
+        .map(|field| format!("`{}`", field))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn describe_kinds(kinds: &[RepresentationKind]) -> String {
+    match kinds {
+        _ if kinds.len() == ANY_KIND.len() => "any kind".to_string(),
+        [] => "nothing".to_string(),
+        [only] => only.to_string(),
+        [init @ .., last] => format!(
+            "{} or {}",
+            init.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            last
+        ),
+    }
+}
+
+impl Schema {
+    /// Checks that the schema is well-formed beyond being syntactically valid
+    ///
+    /// # Errors
+    ///
+    /// Lists every problem found.
+    pub fn check(&self) -> Result<(), Vec<Problem>> {
+        let mut problems = vec![];
+        self.check_references(&mut problems);
+        self.check_copies(&mut problems);
+        self.check_map_keys(&mut problems);
+        self.check_structs(&mut problems);
+        self.check_unions(&mut problems);
+        self.check_enums(&mut problems);
+        self.check_inhabited(&mut problems);
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    fn check_references(&self, problems: &mut Vec<Problem>) {
+        for reference in self.references() {
+            if !self.resolves(reference.target.as_str()) {
+                problems.push(Problem::UndefinedType {
+                    suggestion: self.suggest(reference.target.as_str()),
+                    location: reference.location,
+                    name: reference.target,
+                });
+            }
+        }
+    }
+
+    fn check_copies(&self, problems: &mut Vec<Problem>) {
+        for cycle in self.copy_cycles() {
+            problems.push(Problem::CopyCycle {
+                location: Location::new(&cycle[0]),
+                cycle,
+            });
+        }
+    }
+
+    fn check_map_keys(&self, problems: &mut Vec<Problem>) {
+        for reference in self.references() {
+            if reference.location.path.last() != Some(&Segment::Key) {
+                continue;
+            }
+            match self.representation_kinds(reference.target.as_str()) {
+                Some(kinds) if !is_string(&kinds) => problems.push(Problem::NonStringMapKey {
+                    location: reference.location,
+                    key_type: reference.target,
+                    kinds: kinds.into_iter().collect(),
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    fn check_structs(&self, problems: &mut Vec<Problem>) {
+        for (name, ty) in &self.types {
+            if let Type::Struct(s) = ty {
+                let at = Location::new(name);
+                self.check_struct(s, &at, problems);
+                self.check_implicits(s, &at, problems);
+            }
+        }
+    }
+
+    fn check_struct(&self, s: &TypeStruct, at: &Location, problems: &mut Vec<Problem>) {
+        let (named, field_order, string_representation): (Vec<&FieldName>, _, _) =
+            match &s.representation {
+                StructRepresentation::Map(struct_representation::Map { fields }) => {
+                    (fields.keys().collect(), None, None)
+                }
+                StructRepresentation::Tuple(struct_representation::Tuple { field_order }) => (
+                    field_order.iter().flatten().collect(),
+                    field_order.as_ref(),
+                    None,
+                ),
+                StructRepresentation::StringJoin(struct_representation::StringJoin {
+                    field_order,
+                    ..
+                }) => (
+                    field_order.iter().collect(),
+                    Some(field_order),
+                    Some("stringjoin"),
+                ),
+                StructRepresentation::StringPairs(_) => (vec![], None, Some("stringpairs")),
+                StructRepresentation::ListPairs(_) => (vec![], None, None),
+            };
+
+        let mut reported = BTreeSet::new();
+        for field in named {
+            if !s.fields.contains_key(field) && reported.insert(field) {
+                problems.push(Problem::UndeclaredField {
+                    location: at.clone(),
+                    field: field.clone(),
+                });
+            }
+        }
+
+        if let Some(field_order) = field_order {
+            let missing: Vec<FieldName> = s
+                .fields
+                .keys()
+                .filter(|field| !field_order.contains(field))
+                .cloned()
+                .collect();
+            let mut seen = BTreeSet::new();
+            let repeated: Vec<FieldName> = field_order
+                .iter()
+                .filter(|field| !seen.insert(*field))
+                .cloned()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            if !missing.is_empty() || !repeated.is_empty() {
+                problems.push(Problem::FieldOrderMismatch {
+                    location: at.clone(),
+                    missing,
+                    repeated,
+                });
+            }
+        }
+
+        if let Some(representation) = string_representation {
+            for (name, field) in &s.fields {
+                match self.term_kinds(&field.r#type) {
+                    Some(kinds) if !is_string(&kinds) => problems.push(Problem::NonStringField {
+                        location: at.join(Segment::Field(name.clone())),
+                        representation,
+                        kinds: kinds.into_iter().collect(),
+                    }),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn check_implicits(&self, s: &TypeStruct, at: &Location, problems: &mut Vec<Problem>) {
+        if let StructRepresentation::Map(struct_representation::Map { fields: details }) =
+            &s.representation
+        {
+            for (name, details) in details {
+                // implicit values for undeclared fields are reported along with the field
+                if let (Some(implicit), Some(field)) = (&details.implicit, s.fields.get(name)) {
+                    let location = at.join(Segment::Field(name.clone()));
+                    if field.optional {
+                        problems.push(Problem::ImplicitOptional {
+                            location: location.clone(),
+                        });
+                    }
+                    if self.implicit_fits(&field.r#type, implicit) == Some(false) {
+                        problems.push(Problem::ImplicitMismatch {
+                            location,
+                            implicit: implicit.clone(),
+                            type_term: field.r#type.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether `implicit` is a value of the type `term`, or `None` if the type can't be resolved
+    ///
+    /// Quoted `"true"` and `"false"` are taken as strings when the type is a string or enum.
+    fn implicit_fits(&self, term: &TypeTerm, implicit: &AnyScalar) -> Option<bool> {
+        let name = match term {
+            TypeTerm::TypeName(name) => name,
+            TypeTerm::InlineDefn(_) => return Some(false),
+        };
+
+        Some(
+            match (self.resolve(name.as_str()).ok()?.as_ref(), implicit) {
+                (Type::Bool(_), AnyScalar::Bool(_))
+                | (Type::String(_), AnyScalar::String(_) | AnyScalar::Bool(_))
+                | (Type::Bytes(_), AnyScalar::Bytes(_))
+                | (Type::Int(_), AnyScalar::Int(_))
+                | (Type::Float(_), AnyScalar::Float(_) | AnyScalar::Int(_)) => true,
+                (Type::Enum(e), AnyScalar::String(member)) => {
+                    e.members.contains_key(member.as_str())
+                }
+                (Type::Enum(e), AnyScalar::Bool(b)) => {
+                    e.members.contains_key(b.to_string().as_str())
+                }
+                _ => false,
+            },
+        )
+    }
+
+    fn check_unions(&self, problems: &mut Vec<Problem>) {
+        for (name, ty) in &self.types {
+            if let Type::Union(u) = ty {
+                self.check_union(u, &Location::new(name), problems);
+            }
+        }
+    }
+
+    fn check_union(&self, u: &TypeUnion, at: &Location, problems: &mut Vec<Problem>) {
+        let member_at = |discriminant: String| at.join(Segment::Member(discriminant));
+
+        match &u.representation {
+            UnionRepresentation::Kinded(union_representation::Kinded(table)) => {
+                for (kind, member) in table {
+                    match self.representation_kinds(member.as_str()) {
+                        Some(kinds) if kinds != single(kind.clone()) => {
+                            problems.push(Problem::KindMismatch {
+                                location: member_at(kind.to_string()),
+                                member: member.clone(),
+                                kind: kind.clone(),
+                                kinds: kinds.into_iter().collect(),
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            UnionRepresentation::Keyed(_) => {}
+            UnionRepresentation::Envelope(union_representation::Envelope {
+                discriminant_key,
+                content_key,
+                ..
+            }) => {
+                if discriminant_key == content_key {
+                    problems.push(Problem::EnvelopeKeysCollide {
+                        location: at.clone(),
+                        key: discriminant_key.clone(),
+                    });
+                }
+            }
+            UnionRepresentation::Inline(union_representation::Inline {
+                discriminant_key, ..
+            }) => {
+                for (discriminant, member) in union_members(&u.representation) {
+                    let resolved = self.resolve(member.as_str());
+                    match resolved.as_deref() {
+                        Ok(Type::Struct(TypeStruct {
+                            fields,
+                            representation: StructRepresentation::Map(details),
+                        })) => {
+                            for field in fields.keys() {
+                                let key = details
+                                    .fields
+                                    .get(field)
+                                    .and_then(|details| details.rename.as_deref())
+                                    .unwrap_or_else(|| field.as_str());
+                                if key == discriminant_key {
+                                    problems.push(Problem::DiscriminantKeyCollision {
+                                        location: member_at(discriminant.clone()),
+                                        member: member.clone(),
+                                        field: field.clone(),
+                                        discriminant_key: discriminant_key.clone(),
+                                    });
+                                }
+                            }
+                        }
+                        // undefined types and copy cycles are reported elsewhere
+                        Err(_) => {}
+                        Ok(_) => problems.push(Problem::InlineMemberNotMapStruct {
+                            location: member_at(discriminant),
+                            member: member.clone(),
+                        }),
+                    }
+                }
+            }
+            UnionRepresentation::BytePrefix(union_representation::BytePrefix {
+                discriminant_table,
+            }) => {
+                let mut by_byte: BTreeMap<u8, Vec<TypeName>> = BTreeMap::new();
+                for (member, byte) in discriminant_table {
+                    by_byte.entry(*byte).or_default().push(member.clone());
+                }
+                for (byte, members) in by_byte {
+                    if members.len() > 1 {
+                        problems.push(Problem::DuplicateBytePrefix {
+                            location: at.clone(),
+                            byte,
+                            members,
+                        });
+                    }
+                }
+                for (discriminant, member) in union_members(&u.representation) {
+                    match self.representation_kinds(member.as_str()) {
+                        Some(kinds) if kinds != single(RepresentationKind::Bytes) => {
+                            problems.push(Problem::BytePrefixMemberNotBytes {
+                                location: member_at(discriminant),
+                                member: member.clone(),
+                                kinds: kinds.into_iter().collect(),
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_enums(&self, problems: &mut Vec<Problem>) {
+        for (name, ty) in &self.types {
+            if let Type::Enum(e) = ty {
+                check_enum(e, &Location::new(name), problems);
+            }
+        }
+    }
+
+    fn check_inhabited(&self, problems: &mut Vec<Problem>) {
+        let inhabited = self.inhabited_types();
+        for (name, ty) in &self.types {
+            if !inhabited.contains(name) {
+                let location = Location::new(name);
+                let because = self
+                    .inhabited_by(ty, &location, &inhabited)
+                    .err()
+                    .unwrap_or_default();
+                problems.push(Problem::Uninhabited { location, because });
+            }
+        }
+    }
+
+    /// Names of the declared types which have at least one finite value
+    ///
+    /// Copies within cycles are included to avoid reporting them twice.
+    pub(crate) fn inhabited_types(&self) -> BTreeSet<TypeName> {
+        self.inhabitation_order().into_iter().collect()
+    }
+
+    /// Names of the declared types which have at least one finite value, each after the types a finite value of it
+    /// can be built from
+    ///
+    /// Copies within cycles come first, in no particular order.
+    pub(crate) fn inhabitation_order(&self) -> Vec<TypeName> {
+        let mut order: Vec<TypeName> = self.copy_cycles().into_iter().flatten().collect();
+        let mut inhabited: BTreeSet<TypeName> = order.iter().cloned().collect();
+
+        // a type is inhabited once the types its values need are, so iterate until nothing changes
+        loop {
+            let before = inhabited.len();
+            for (name, ty) in &self.types {
+                if !inhabited.contains(name)
+                    && self
+                        .inhabited_by(ty, &Location::new(name), &inhabited)
+                        .is_ok()
+                {
+                    inhabited.insert(name.clone());
+                    order.push(name.clone());
+                }
+            }
+            if inhabited.len() == before {
+                return order;
+            }
+        }
+    }
+
+    /// Whether a value of `ty` can be constructed from values of `inhabited` types alone, or else the references
+    /// preventing it
+    fn inhabited_by(
+        &self,
+        ty: &Type,
+        at: &Location,
+        inhabited: &BTreeSet<TypeName>,
+    ) -> Result<(), Vec<Reference>> {
+        // undefined and prelude types are assumed to be inhabited
+        let has_values =
+            |name: &TypeName| inhabited.contains(name) || self.types.get(name.as_str()).is_none();
+        let reference = |location: Location, target: &TypeName| Reference {
+            location,
+            target: target.clone(),
+        };
+
+        let blocked: Vec<Reference> = match ty {
+            Type::Enum(e) if e.members.is_empty() => return Err(vec![]),
+            Type::Copy(c) if !has_values(&c.from_type) => vec![reference(at.clone(), &c.from_type)],
+            Type::Bool(_)
+            | Type::String(_)
+            | Type::Bytes(_)
+            | Type::Int(_)
+            | Type::Float(_)
+            | Type::Map(_)
+            | Type::List(_)
+            | Type::Link(_)
+            | Type::Enum(_)
+            | Type::Copy(_) => vec![],
+            Type::Union(u) => {
+                let members = union_members(&u.representation);
+                if members.iter().any(|(_, member)| has_values(member)) {
+                    vec![]
+                } else {
+                    return Err(members
+                        .into_iter()
+                        .map(|(discriminant, member)| {
+                            reference(at.join(Segment::Member(discriminant)), member)
+                        })
+                        .collect());
+                }
+            }
+            // collections can always be empty, so only required fields named directly can be a problem
+            Type::Struct(s) => s
+                .fields
+                .iter()
+                .filter(|(_, field)| !field.optional && !field.nullable)
+                .filter_map(|(name, field)| match &field.r#type {
+                    TypeTerm::TypeName(target) if !has_values(target) => {
+                        Some(reference(at.join(Segment::Field(name.clone())), target))
+                    }
+                    _ => None,
+                })
+                .collect(),
+        };
+
+        if blocked.is_empty() {
+            Ok(())
+        } else {
+            Err(blocked)
+        }
+    }
+
+    fn term_kinds(&self, term: &TypeTerm) -> Option<BTreeSet<RepresentationKind>> {
+        match term {
+            TypeTerm::TypeName(name) => self.representation_kinds(name.as_str()),
+            TypeTerm::InlineDefn(inline) => match inline.as_ref() {
+                InlineDefn::Map(m) => map_kind(m).map(single),
+                InlineDefn::List(_) => Some(single(RepresentationKind::List)),
+            },
+        }
+    }
+
+    /// Kinds which values of the named type may be represented as in the Data Model, following copies
+    ///
+    /// `None` if they can't be determined because the name is undefined, part of a copy cycle or uses an advanced
+    /// data layout.
+    pub(crate) fn representation_kinds(&self, name: &str) -> Option<BTreeSet<RepresentationKind>> {
+        type_kinds(self.resolve(name).ok()?.as_ref())
+    }
+
+    /// Finds the declared or prelude type name closest to `name`, if any is close enough to be a plausible typo
+    fn suggest(&self, name: &str) -> Option<TypeName> {
+        let threshold = (name.chars().count() / 3).max(1);
+
+        self.types
+            .names()
+            .map(TypeName::as_str)
+            .chain(PRELUDE.iter().copied())
+            .map(|candidate| (edit_distance(name, candidate), candidate))
+            .filter(|(distance, _)| *distance <= threshold)
+            .min()
+            .map(|(_, candidate)| TypeName(candidate.to_string()))
+    }
+}
+
+fn check_enum(e: &TypeEnum, at: &Location, problems: &mut Vec<Problem>) {
+    let undeclared = |member: &EnumValue| Problem::UndeclaredEnumMember {
+        location: at.clone(),
+        member: member.clone(),
+    };
+
+    // members sharing a value, keyed by the value as it appears in the DSL
+    let mut by_value: BTreeMap<String, Vec<EnumValue>> = BTreeMap::new();
+
+    match &e.representation {
+        EnumRepresentation::String(enum_representation::String(table)) => {
+            problems.extend(
+                table
+                    .keys()
+                    .filter(|m| !e.members.contains_key(*m))
+                    .map(undeclared),
+            );
+            for member in e.members.keys() {
+                let value = table.get(member).map_or(member.as_str(), String::as_str);
+                by_value
+                    .entry(format!("{:?}", value))
+                    .or_default()
+                    .push(member.clone());
+            }
+        }
+        EnumRepresentation::Int(enum_representation::Int(table)) => {
+            problems.extend(
+                table
+                    .keys()
+                    .filter(|m| !e.members.contains_key(*m))
+                    .map(undeclared),
+            );
+            for member in e.members.keys() {
+                match table.get(member) {
+                    Some(value) => {
+                        if !SAFE_INT_RANGE.contains(value) {
+                            problems.push(Problem::EnumIntOutOfRange {
+                                location: at.clone(),
+                                member: member.clone(),
+                                value: *value,
+                            });
+                        }
+                        by_value
+                            .entry(value.to_string())
+                            .or_default()
+                            .push(member.clone());
+                    }
+                    None => problems.push(Problem::MissingEnumInt {
+                        location: at.clone(),
+                        member: member.clone(),
+                    }),
+                }
+            }
+        }
+    }
+
+    for (value, members) in by_value {
+        if members.len() > 1 {
+            problems.push(Problem::DuplicateEnumRepresentation {
+                location: at.clone(),
+                value,
+                members,
+            });
+        }
+    }
+}
+
+/// Whether values represented as any of `kinds` are always strings
+pub(crate) fn is_string(kinds: &BTreeSet<RepresentationKind>) -> bool {
+    kinds.len() == 1 && kinds.contains(&RepresentationKind::String)
+}
+
+fn type_kinds(ty: &Type) -> Option<BTreeSet<RepresentationKind>> {
+    use RepresentationKind as K;
+
+    let kind = match ty {
+        Type::Bool(_) => K::Bool,
+        Type::String(_) => K::String,
+        Type::Bytes(b) => match b.representation {
+            BytesRepresentation::Bytes(_) => K::Bytes,
+            BytesRepresentation::Advanced(_) => return None,
+        },
+        Type::Int(_) => K::Int,
+        Type::Float(_) => K::Float,
+        Type::Map(m) => return map_kind(m).map(single),
+        Type::List(_) => K::List,
+        Type::Link(_) => K::Link,
+        Type::Union(u) => match &u.representation {
+            UnionRepresentation::Kinded(kinded) => return Some(kinded.0.keys().cloned().collect()),
+            UnionRepresentation::Keyed(_)
+            | UnionRepresentation::Envelope(_)
+            | UnionRepresentation::Inline(_) => K::Map,
+            UnionRepresentation::BytePrefix(_) => K::Bytes,
+        },
+        Type::Struct(s) => match s.representation {
+            StructRepresentation::Map(_) => K::Map,
+            StructRepresentation::Tuple(_) | StructRepresentation::ListPairs(_) => K::List,
+            StructRepresentation::StringPairs(_) | StructRepresentation::StringJoin(_) => K::String,
+        },
+        Type::Enum(e) => match e.representation {
+            EnumRepresentation::String(_) => K::String,
+            EnumRepresentation::Int(_) => K::Int,
+        },
+        Type::Copy(_) => return None,
+    };
+    Some(single(kind))
+}
+
+fn map_kind(map: &TypeMap) -> Option<RepresentationKind> {
+    match map.representation {
+        MapRepresentation::Map(_) => Some(RepresentationKind::Map),
+        MapRepresentation::StringPairs(_) => Some(RepresentationKind::String),
+        MapRepresentation::ListPairs(_) => Some(RepresentationKind::List),
+        MapRepresentation::Advanced(_) => None,
+    }
+}
+
+fn single(kind: RepresentationKind) -> BTreeSet<RepresentationKind> {
+    std::iter::once(kind).collect()
+}
+
+/// Levenshtein distance between `a` and `b`, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::schema::SchemaBuilder;
+
+    use pretty_assertions::assert_eq;
+
+    fn problems(dsl: &str) -> Vec<String> {
+        Schema::from_dsl(dsl)
+            .unwrap()
+            .check()
+            .err()
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("Foo", "Foo"), 0);
+        assert_eq!(edit_distance("Foo", "Fo"), 1);
+        assert_eq!(edit_distance("Foo", "Bar"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn prelude_types_resolve() {
+        assert_eq!(
+            problems("type Foo {String:Any}\n\ntype Bar [Int]\n\ntype Baz &Any\n"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn undefined_types_are_reported_with_suggestions() {
+        assert_eq!(
+            problems(
+                "type Foo {String:Missing}\n\ntype Bar struct {\n  x Nope\n  y Fooo\n}\n\ntype Baz = Bar\n\ntype Qux &Bax\n"
+            ),
+            [
+                "undefined type `Nope` referenced at `Bar.x`",
+                "undefined type `Fooo` referenced at `Bar.y`; did you mean `Foo`?",
+                "undefined type `Missing` referenced at `Foo[value]`",
+                "undefined type `Bax` referenced at `Qux`; did you mean `Bar`?",
+            ]
+        );
+    }
+
+    #[test]
+    fn copy_cycles_are_reported_once() {
+        assert_eq!(
+            problems("type A = B\n\ntype B = C\n\ntype C = B\n\ntype D = D\n\ntype E = Missing\n"),
+            [
+                "undefined type `Missing` referenced at `E`",
+                "copy `B` never reaches a type because copies form a cycle: `B` = `C` = `B`",
+                "copy `D` never reaches a type because copies form a cycle: `D` = `D`",
+            ]
+        );
+    }
+
+    #[test]
+    fn implicit_values_must_fit_their_fields() {
+        assert_eq!(
+            problems(concat!(
+                "type E enum {\n  | yes\n  | no\n}\n\n",
+                "type Name = String\n\n",
+                "type S struct {\n",
+                "  a Bool (implicit \"false\")\n",
+                "  b Name (implicit \"true\")\n",
+                "  c Int (implicit -3)\n",
+                "  d Float (implicit 1)\n",
+                "  e Bytes (implicit x\"00ff\")\n",
+                "  f E (implicit \"yes\")\n",
+                "  g E (implicit \"maybe\")\n",
+                "  h Int (implicit 1.5)\n",
+                "  i optional String (implicit \"\")\n",
+                "  j [Int] (implicit 0)\n",
+                "}\n",
+            )),
+            [
+                "implicit value \"maybe\" of field `S.g` isn't a value of its type `E`",
+                "implicit value 1.5 of field `S.h` isn't a value of its type `Int`",
+                "field `S.i` can't both be optional and have an implicit value",
+                "implicit value 0 of field `S.j` isn't a value of its type `[Int]`",
+            ]
+        );
+    }
+
+    #[test]
+    fn enum_representations_must_match_members() {
+        let members = || {
+            ["a", "b", "c"]
+                .iter()
+                .map(|m| m.parse::<EnumValue>().unwrap())
+        };
+        let table = |entries: &[(&str, Int)]| -> BTreeMap<EnumValue, Int> {
+            entries
+                .iter()
+                .map(|(member, value)| (member.parse().unwrap(), *value))
+                .collect()
+        };
+
+        let mut builder = SchemaBuilder::new();
+        builder
+            .add_type("S".parse().unwrap(), TypeEnum::new(members()))
+            .unwrap()
+            .set_representation(
+                "S",
+                EnumRepresentation::String(enum_representation::String(
+                    vec![("a", "b"), ("z", "x")]
+                        .into_iter()
+                        .map(|(member, value)| (member.parse().unwrap(), value.to_string()))
+                        .collect(),
+                )),
+            )
+            .unwrap()
+            .add_type("I".parse().unwrap(), TypeEnum::new(members()))
+            .unwrap()
+            .set_representation(
+                "I",
+                EnumRepresentation::Int(enum_representation::Int(table(&[
+                    ("a", 1),
+                    ("b", 1 << 53),
+                    ("y", 2),
+                ]))),
+            )
+            .unwrap()
+            .add_type("Ok".parse().unwrap(), TypeEnum::new(members()))
+            .unwrap()
+            .set_representation(
+                "Ok",
+                EnumRepresentation::Int(enum_representation::Int(table(&[
+                    ("a", -1),
+                    ("b", 0),
+                    ("c", (1 << 53) - 1),
+                ]))),
+            )
+            .unwrap();
+
+        assert_eq!(
+            builder
+                .build()
+                .unwrap()
+                .check()
+                .unwrap_err()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "representation of `I` refers to undeclared member `y`",
+                "member `b` of `I` is represented as 9007199254740992, outside the range of integers every IPLD implementation can represent (-9007199254740991 to 9007199254740991)",
+                "int-represented enum `I` has no integer for member `c`",
+                "representation of `S` refers to undeclared member `z`",
+                "enum `S` represents members `a`, `b` with the same value \"b\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn types_without_finite_values_are_reported() {
+        assert_eq!(
+            problems(concat!(
+                "type Node struct {\n  next Node\n}\n\n",
+                "type Ok1 struct {\n  next nullable Ok1\n  children [Ok1]\n  parent Ptr\n}\n\n",
+                "type Ptr &Ok1\n\n",
+                "type Ok2 struct {\n  next optional Ok2\n}\n\n",
+                "type Wrapper = Node\n\n",
+                "type A union {\n  | Node \"node\"\n  | Wrapper \"wrapper\"\n} representation keyed\n\n",
+                "type Ok3 union {\n  | Node \"node\"\n  | Ok2 \"ok\"\n} representation keyed\n\n",
+                "type E union {\n} representation keyed\n",
+            )),
+            [
+                "type `A` can never have a value because `Node` at `A[member \"node\"]` and `Wrapper` at `A[member \"wrapper\"]` can't have one either",
+                "type `E` can never have a value because it has no members",
+                "type `Node` can never have a value because `Node` at `Node.next` can't have one either",
+                "type `Wrapper` can never have a value because `Node` at `Wrapper` can't have one either",
+            ]
+        );
+    }
+
+    #[test]
+    fn map_keys_must_be_represented_as_strings() {
+        assert_eq!(
+            problems(
+                "type E enum {\n  | a\n}\n\ntype S string\n\ntype K = S\n\ntype Ok1 {String:Int}\n\ntype Ok2 {K:Int}\n\ntype Ok3 {E:Int}\n\ntype Bad1 {Int:Int}\n\ntype Bad2 {Ok1:Int}\n\ntype Bad3 [{Any:Int}]\n\ntype U union {\n  | S string\n  | Ok1 map\n} representation kinded\n\ntype Bad4 {U:Int}\n"
+            ),
+            [
+                "map key type `Int` at `Bad1[key]` is represented as int but map keys must be represented as string",
+                "map key type `Ok1` at `Bad2[key]` is represented as map but map keys must be represented as string",
+                "map key type `Any` at `Bad3[value][key]` is represented as any kind but map keys must be represented as string",
+                "map key type `U` at `Bad4[key]` is represented as string or map but map keys must be represented as string",
+            ]
+        );
+    }
+
+    #[test]
+    fn struct_representations_must_agree_with_fields() {
+        let mut schema = Schema::from_dsl(
+            "type T struct {\n  a Int\n  b String\n} representation tuple {\n  fieldOrder [\"a\", \"c\", \"a\"]\n}\n\ntype J struct {\n  a String\n  b Int\n  c {String:String}\n} representation stringjoin {\n  join \":\"\n}\n",
+        )
+        .unwrap();
+        if let Some(Type::Struct(s)) = schema.types.0.get_mut("J") {
+            if let StructRepresentation::StringJoin(j) = &mut s.representation {
+                j.field_order.push(FieldName("z".to_string()));
+            }
+        }
+
+        assert_eq!(
+            schema
+                .check()
+                .unwrap_err()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "representation of `J` refers to undeclared field `z`",
+                "field `J.b` is represented as int but fields of stringjoin structs must be represented as string",
+                "field `J.c` is represented as map but fields of stringjoin structs must be represented as string",
+                "representation of `T` refers to undeclared field `c`",
+                "field order of `T` must list each declared field exactly once; missing `b`; repeated `a`",
+            ]
+        );
+    }
+
+    #[test]
+    fn union_representations_must_follow_the_rules() {
+        assert_eq!(
+            problems(concat!(
+                "type S struct {\n  tag String\n  x Int\n}\n\n",
+                "type C = S\n\n",
+                "type K union {\n  | String string\n  | S int\n  | Any link\n} representation kinded\n\n",
+                "type I union {\n  | C \"c\"\n  | Int \"i\"\n} representation inline {\n  discriminantKey \"tag\"\n}\n\n",
+                "type E union {\n  | S \"s\"\n} representation envelope {\n  discriminantKey \"k\"\n  contentKey \"k\"\n}\n\n",
+                "type B union {\n  | Bytes 1\n  | S 1\n  | Int 2\n} representation byteprefix\n",
+            )),
+            [
+                "byteprefix union `B` uses byte 1 for more than one member: `Bytes`, `S`",
+                "member `Int` at `B[member 2]` of a byteprefix union is represented as int but must be bytes",
+                "member `S` at `B[member 1]` of a byteprefix union is represented as map but must be bytes",
+                "envelope union `E` uses \"k\" as both its discriminant key and content key",
+                "member `C` at `I[member \"c\"]` has field `tag` serialized under the union's discriminant key \"tag\"",
+                "member `Int` at `I[member \"i\"]` of an inline union must be a struct represented as a map",
+                "member `S` at `K[member int]` is represented as map but is listed as int",
+                "member `Any` at `K[member link]` is represented as any kind but is listed as link",
+            ]
+        );
+    }
+}
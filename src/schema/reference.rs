@@ -0,0 +1,319 @@
+use std::{collections::BTreeSet, fmt};
+
+use super::{
+    union_representation, FieldName, InlineDefn, Schema, Type, TypeLink, TypeMap, TypeName,
+    TypeTerm, UnionRepresentation,
+};
+
+/// Names of the types every schema can refer to without declaring them
+pub const PRELUDE: [&str; 9] = [
+    "Any", "Bool", "Bytes", "Float", "Int", "Link", "List", "Map", "String",
+];
+
+/// A step from a type's declaration into its definition
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Segment {
+    /// Key type of a map
+    Key,
+    /// Value type of a map or list
+    Value,
+    /// Type of a struct field
+    Field(FieldName),
+    /// Union member, identified by its discriminant
+    Member(String),
+}
+
+/// A place within a schema, e.g. the value type of field `x` in the declaration of `Foo`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    /// Name of the declared type the place is within
+    pub type_name: TypeName,
+    /// Steps from the declaration to the place, empty for the declaration itself
+    pub path: Vec<Segment>,
+}
+
+impl Location {
+    pub(crate) fn new(type_name: &TypeName) -> Self {
+        Self {
+            type_name: type_name.clone(),
+            path: vec![],
+        }
+    }
+
+    pub(crate) fn join(&self, segment: Segment) -> Self {
+        let mut path = self.path.clone();
+        path.push(segment);
+        Self {
+            type_name: self.type_name.clone(),
+            path,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.type_name)?;
+        for segment in &self.path {
+            match segment {
+                Segment::Key => write!(f, "[key]")?,
+                Segment::Value => write!(f, "[value]")?,
+                Segment::Field(name) => write!(f, ".{}", name)?,
+                Segment::Member(discriminant) => write!(f, "[member {}]", discriminant)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Use of a type's name within the definition of a type
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reference {
+    /// Where the name is used
+    pub location: Location,
+    /// The type named
+    pub target: TypeName,
+}
+
+impl Schema {
+    /// Lists every place a type is referred to by name, ordered by location
+    #[must_use]
+    pub fn references(&self) -> Vec<Reference> {
+        let mut refs = vec![];
+        for (name, ty) in &self.types {
+            type_references(ty, &Location::new(name), &mut refs);
+        }
+        refs
+    }
+
+    /// Names of the declared types no other type refers to, which data is most likely to be a value of
+    ///
+    /// Types referring only to themselves are included.
+    #[must_use]
+    pub fn roots(&self) -> Vec<&TypeName> {
+        let referenced: BTreeSet<TypeName> = self
+            .references()
+            .into_iter()
+            .filter(|reference| reference.location.type_name != reference.target)
+            .map(|reference| reference.target)
+            .collect();
+        self.types
+            .names()
+            .filter(|name| !referenced.contains(*name))
+            .collect()
+    }
+
+    /// Whether `name` refers to a declared type or one from the [`PRELUDE`]
+    #[must_use]
+    pub fn resolves(&self, name: &str) -> bool {
+        self.types.get(name).is_some() || PRELUDE.contains(&name)
+    }
+}
+
+fn type_references(ty: &Type, at: &Location, refs: &mut Vec<Reference>) {
+    let mut push = |location: Location, target: &TypeName| {
+        refs.push(Reference {
+            location,
+            target: target.clone(),
+        });
+    };
+
+    match ty {
+        Type::Bool(_)
+        | Type::String(_)
+        | Type::Bytes(_)
+        | Type::Int(_)
+        | Type::Float(_)
+        | Type::Enum(_) => {}
+        Type::Map(m) => {
+            push(at.join(Segment::Key), &m.key_type);
+            term_references(&m.value_type, &at.join(Segment::Value), refs);
+        }
+        Type::List(l) => term_references(&l.value_type, &at.join(Segment::Value), refs),
+        Type::Link(l) => push(at.clone(), &link_target(l)),
+        Type::Union(u) => {
+            for (discriminant, member) in union_members(&u.representation) {
+                push(at.join(Segment::Member(discriminant)), member);
+            }
+        }
+        Type::Struct(s) => {
+            for (name, field) in &s.fields {
+                term_references(&field.r#type, &at.join(Segment::Field(name.clone())), refs);
+            }
+        }
+        Type::Copy(c) => push(at.clone(), &c.from_type),
+    }
+}
+
+fn term_references(term: &TypeTerm, at: &Location, refs: &mut Vec<Reference>) {
+    match term {
+        TypeTerm::TypeName(name) => refs.push(Reference {
+            location: at.clone(),
+            target: name.clone(),
+        }),
+        TypeTerm::InlineDefn(inline) => match inline.as_ref() {
+            InlineDefn::Map(m) => type_references(&Type::Map(m.clone()), at, refs),
+            InlineDefn::List(l) => type_references(&Type::List(l.clone()), at, refs),
+        },
+    }
+}
+
+fn link_target(link: &TypeLink) -> TypeName {
+    TypeName(link.expected_type.clone())
+}
+
+/// Pairs each union member with the discriminant used to select it
+pub(crate) fn union_members(repr: &UnionRepresentation) -> Vec<(String, &TypeName)> {
+    match repr {
+        UnionRepresentation::Kinded(union_representation::Kinded(table)) => table
+            .iter()
+            .map(|(kind, name)| (kind.to_string(), name))
+            .collect(),
+        UnionRepresentation::Keyed(union_representation::Keyed(table))
+        | UnionRepresentation::Envelope(union_representation::Envelope {
+            discriminant_table: table,
+            ..
+        })
+        | UnionRepresentation::Inline(union_representation::Inline {
+            discriminant_table: table,
+            ..
+        }) => table
+            .iter()
+            .map(|(key, name)| (format!("{:?}", key), name))
+            .collect(),
+        UnionRepresentation::BytePrefix(union_representation::BytePrefix {
+            discriminant_table,
+        }) => discriminant_table
+            .iter()
+            .map(|(name, byte)| (byte.to_string(), name))
+            .collect(),
+    }
+}
+
+impl Type {
+    /// Replaces every reference to `from` within the type's definition with `to`
+    pub(crate) fn rename_references(&mut self, from: &str, to: &TypeName) {
+        let rename = |name: &mut TypeName| {
+            if name.0 == from {
+                *name = to.clone();
+            }
+        };
+
+        match self {
+            Self::Bool(_)
+            | Self::String(_)
+            | Self::Bytes(_)
+            | Self::Int(_)
+            | Self::Float(_)
+            | Self::Enum(_) => {}
+            Self::Map(m) => m.rename_references(from, to),
+            Self::List(l) => l.value_type.rename_references(from, to),
+            Self::Link(l) => {
+                if l.expected_type == from {
+                    l.expected_type.clone_from(&to.0);
+                }
+            }
+            Self::Union(u) => match &mut u.representation {
+                UnionRepresentation::Kinded(union_representation::Kinded(table)) => {
+                    table.values_mut().for_each(rename);
+                }
+                UnionRepresentation::Keyed(union_representation::Keyed(table))
+                | UnionRepresentation::Envelope(union_representation::Envelope {
+                    discriminant_table: table,
+                    ..
+                })
+                | UnionRepresentation::Inline(union_representation::Inline {
+                    discriminant_table: table,
+                    ..
+                }) => table.values_mut().for_each(rename),
+                UnionRepresentation::BytePrefix(union_representation::BytePrefix {
+                    discriminant_table,
+                }) => {
+                    *discriminant_table = std::mem::take(discriminant_table)
+                        .into_iter()
+                        .map(|(mut name, byte)| {
+                            rename(&mut name);
+                            (name, byte)
+                        })
+                        .collect();
+                }
+            },
+            Self::Struct(s) => {
+                for field in s.fields.values_mut() {
+                    field.r#type.rename_references(from, to);
+                }
+            }
+            Self::Copy(c) => rename(&mut c.from_type),
+        }
+    }
+}
+
+impl TypeTerm {
+    fn rename_references(&mut self, from: &str, to: &TypeName) {
+        match self {
+            Self::TypeName(name) => {
+                if name.0 == from {
+                    *name = to.clone();
+                }
+            }
+            Self::InlineDefn(inline) => match inline.as_mut() {
+                InlineDefn::Map(m) => m.rename_references(from, to),
+                InlineDefn::List(l) => l.value_type.rename_references(from, to),
+            },
+        }
+    }
+}
+
+impl TypeMap {
+    fn rename_references(&mut self, from: &str, to: &TypeName) {
+        if self.key_type.0 == from {
+            self.key_type = to.clone();
+        }
+        self.value_type.rename_references(from, to);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn references_are_located() {
+        let schema = Schema::from_dsl(
+            "type Foo struct {\n  x nullable {String:[Bar]}\n}\n\ntype Bar = Foo\n\ntype U union {\n  | Foo \"foo\"\n} representation keyed\n\ntype L &Foo\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            schema
+                .references()
+                .into_iter()
+                .map(|r| format!("{} -> {}", r.location, r.target))
+                .collect::<Vec<_>>(),
+            [
+                "Bar -> Foo",
+                "Foo.x[key] -> String",
+                "Foo.x[value][value] -> Bar",
+                "L -> Foo",
+                "U[member \"foo\"] -> Foo",
+            ]
+        );
+    }
+
+    #[test]
+    fn roots_are_only_referred_to_by_themselves() {
+        let schema = Schema::from_dsl(
+            "type Tree struct {\n  children [Tree]\n  label Label\n}\n\ntype Label string\n\ntype Other [Label]\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            schema.roots(),
+            [
+                &"Other".parse::<TypeName>().unwrap(),
+                &"Tree".parse().unwrap()
+            ]
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Adjustments which turn arbitrary sets of types into well-formed schemas, so that generated schemas pass
+//! [`Schema::check`]
+//!
+//! Each repair is deterministic, keeping generation reproducible from a seed.
+
+use std::collections::BTreeSet;
+
+use super::{
+    check::is_string, resolve::prelude_type, union_representation, AdvancedDataLayoutMap,
+    InlineDefn, Problem, RepresentationKind, Schema, SchemaMap, Type, TypeCopy, TypeMap, TypeName,
+    TypeTerm, TypeUnion, UnionRepresentation, PRELUDE,
+};
+
+pub(crate) fn well_formed(types: SchemaMap) -> SchemaMap {
+    let mut schema = Schema {
+        types,
+        advanced: AdvancedDataLayoutMap::default(),
+    };
+    resolve_references(&mut schema);
+    break_copy_cycles(&mut schema);
+    union_rules(&mut schema);
+    byte_prefix_members(&mut schema);
+    inhabit_unions(&mut schema);
+    string_map_keys(&mut schema);
+    schema.types
+}
+
+/// Points every reference to an undefined type at a declared or prelude type instead
+fn resolve_references(schema: &mut Schema) {
+    let candidates: Vec<TypeName> = schema
+        .types
+        .names()
+        .cloned()
+        .chain(PRELUDE.iter().map(|name| TypeName(name.to_string())))
+        .collect();
+    let undefined: BTreeSet<TypeName> = schema
+        .references()
+        .into_iter()
+        .map(|reference| reference.target)
+        .filter(|target| !schema.resolves(target.as_str()))
+        .collect();
+
+    for from in undefined {
+        let to = &candidates[pick(from.as_str(), candidates.len())];
+        for ty in schema.types.0.values_mut() {
+            ty.rename_references(from.as_str(), to);
+        }
+    }
+}
+
+/// Chooses an index below `len` determined by `name`
+fn pick(name: &str, len: usize) -> usize {
+    name.bytes().map(usize::from).sum::<usize>() % len
+}
+
+/// Turns the first copy in each cycle of copies into a copy of a prelude type
+fn break_copy_cycles(schema: &mut Schema) {
+    for cycle in schema.copy_cycles() {
+        let to = TypeName(PRELUDE[pick(cycle[0].as_str(), PRELUDE.len())].to_string());
+        if let Some(ty) = schema.types.0.get_mut(&cycle[0]) {
+            *ty = Type::Copy(TypeCopy::new(to));
+        }
+    }
+}
+
+/// Fixes unions breaking the rules of their representation strategy
+fn union_rules(schema: &mut Schema) {
+    for problem in schema.check().err().unwrap_or_default() {
+        let type_name = match &problem {
+            Problem::KindMismatch { location, .. }
+            | Problem::InlineMemberNotMapStruct { location, .. }
+            | Problem::DiscriminantKeyCollision { location, .. }
+            | Problem::EnvelopeKeysCollide { location, .. }
+            | Problem::DuplicateBytePrefix { location, .. } => location.type_name.clone(),
+            _ => continue,
+        };
+        if let Some(Type::Union(TypeUnion { representation })) = schema.types.0.get_mut(&type_name)
+        {
+            repair_union(problem, representation);
+        }
+    }
+}
+
+fn repair_union(problem: Problem, representation: &mut UnionRepresentation) {
+    match (problem, representation) {
+        (
+            Problem::KindMismatch { kind, .. },
+            UnionRepresentation::Kinded(union_representation::Kinded(table)),
+        ) => {
+            let member = prelude_type(&kind);
+            table.insert(kind, member);
+        }
+        (
+            Problem::InlineMemberNotMapStruct { .. } | Problem::DiscriminantKeyCollision { .. },
+            representation,
+        ) => {
+            // keyed unions have no constraints on their members
+            if let UnionRepresentation::Inline(inline) = representation {
+                let table = std::mem::take(&mut inline.discriminant_table);
+                *representation = UnionRepresentation::Keyed(union_representation::Keyed(table));
+            }
+        }
+        (Problem::EnvelopeKeysCollide { .. }, UnionRepresentation::Envelope(envelope)) => {
+            envelope.content_key.push('_');
+        }
+        (
+            Problem::DuplicateBytePrefix { members, .. },
+            UnionRepresentation::BytePrefix(union_representation::BytePrefix {
+                discriminant_table: table,
+            }),
+        ) => {
+            for member in members.into_iter().skip(1) {
+                match (0..=u8::MAX).find(|byte| !table.values().any(|b| b == byte)) {
+                    Some(byte) => table.insert(member, byte),
+                    None => table.remove(&member),
+                };
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Drops byteprefix union members which aren't represented as bytes, since only bytes can follow the prefix, giving
+/// their first byte to `Bytes` if no member is left
+fn byte_prefix_members(schema: &mut Schema) {
+    let bytes: BTreeSet<RepresentationKind> = std::iter::once(RepresentationKind::Bytes).collect();
+    let non_bytes: BTreeSet<TypeName> = schema
+        .types
+        .iter()
+        .filter_map(|(_, ty)| match ty {
+            Type::Union(TypeUnion {
+                representation: UnionRepresentation::BytePrefix(byte_prefix),
+            }) => Some(byte_prefix.discriminant_table.keys()),
+            _ => None,
+        })
+        .flatten()
+        .filter(|member| schema.representation_kinds(member.as_str()).as_ref() != Some(&bytes))
+        .cloned()
+        .collect();
+
+    for ty in schema.types.0.values_mut() {
+        if let Type::Union(TypeUnion {
+            representation:
+                UnionRepresentation::BytePrefix(union_representation::BytePrefix {
+                    discriminant_table: table,
+                }),
+        }) = ty
+        {
+            let first = table.values().next().copied();
+            table.retain(|member, _| !non_bytes.contains(member));
+            if let (true, Some(byte)) = (table.is_empty(), first) {
+                table.insert(TypeName("Bytes".to_string()), byte);
+            }
+        }
+    }
+}
+
+/// Gives every union without values a member which has them
+fn inhabit_unions(schema: &mut Schema) {
+    let inhabited = schema.inhabited_types();
+    for (name, ty) in &mut schema.types.0 {
+        if let (false, Type::Union(TypeUnion { representation })) = (inhabited.contains(name), ty) {
+            inhabit_union(representation);
+        }
+    }
+}
+
+fn inhabit_union(representation: &mut UnionRepresentation) {
+    let string = || TypeName("String".to_string());
+
+    match representation {
+        UnionRepresentation::Kinded(union_representation::Kinded(table)) => {
+            let kind = table
+                .keys()
+                .next()
+                .cloned()
+                .unwrap_or(RepresentationKind::String);
+            table.insert(kind.clone(), prelude_type(&kind));
+        }
+        UnionRepresentation::Keyed(union_representation::Keyed(table))
+        | UnionRepresentation::Envelope(union_representation::Envelope {
+            discriminant_table: table,
+            ..
+        }) => match table.values_mut().next() {
+            Some(member) => *member = string(),
+            None => {
+                table.insert(String::new(), string());
+            }
+        },
+        // only structs can be members, and none are generated
+        UnionRepresentation::Inline(inline) => {
+            let table = std::mem::take(&mut inline.discriminant_table);
+            *representation = UnionRepresentation::Keyed(union_representation::Keyed(table));
+            inhabit_union(representation);
+        }
+        UnionRepresentation::BytePrefix(union_representation::BytePrefix {
+            discriminant_table: table,
+        }) => {
+            let byte = table.values().next().copied().unwrap_or(0);
+            if let Some(member) = table.keys().next().cloned() {
+                table.remove(&member);
+            }
+            table.insert(TypeName("Bytes".to_string()), byte);
+        }
+    }
+}
+
+/// Replaces map key types which aren't represented as strings with `String`
+fn string_map_keys(schema: &mut Schema) {
+    let string_represented: BTreeSet<TypeName> = schema
+        .references()
+        .into_iter()
+        .map(|reference| reference.target)
+        .filter(|target| {
+            schema
+                .representation_kinds(target.as_str())
+                .is_some_and(|kinds| is_string(&kinds))
+        })
+        .collect();
+
+    let mut repair = |map: &mut TypeMap| {
+        if !string_represented.contains(&map.key_type) {
+            map.key_type = TypeName("String".to_string());
+        }
+    };
+    for ty in schema.types.0.values_mut() {
+        for_each_map(ty, &mut repair);
+    }
+}
+
+/// Calls `f` with every map type within `ty`, including inline definitions
+fn for_each_map(ty: &mut Type, f: &mut impl FnMut(&mut TypeMap)) {
+    match ty {
+        Type::Map(m) => map_and_nested(m, f),
+        Type::List(l) => term_maps(&mut l.value_type, f),
+        Type::Struct(s) => {
+            for field in s.fields.values_mut() {
+                term_maps(&mut field.r#type, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn map_and_nested(map: &mut TypeMap, f: &mut impl FnMut(&mut TypeMap)) {
+    f(map);
+    term_maps(&mut map.value_type, f);
+}
+
+fn term_maps(term: &mut TypeTerm, f: &mut impl FnMut(&mut TypeMap)) {
+    if let TypeTerm::InlineDefn(inline) = term {
+        match inline.as_mut() {
+            InlineDefn::Map(m) => map_and_nested(m, f),
+            InlineDefn::List(l) => term_maps(&mut l.value_type, f),
+        }
+    }
+}
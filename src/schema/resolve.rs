@@ -0,0 +1,188 @@
+use std::{borrow::Cow, collections::BTreeSet, fmt};
+
+use super::{
+    union_representation, BytesRepresentation, RepresentationKind, Schema, Type, TypeBool,
+    TypeBytes, TypeFloat, TypeInt, TypeLink, TypeList, TypeMap, TypeName, TypeString, TypeUnion,
+    UnionRepresentation,
+};
+
+/// Every kind in the Data Model, each of which values of type `Any` may be represented as
+pub(crate) const ANY_KIND: [RepresentationKind; 8] = [
+    RepresentationKind::Bool,
+    RepresentationKind::String,
+    RepresentationKind::Bytes,
+    RepresentationKind::Int,
+    RepresentationKind::Float,
+    RepresentationKind::Map,
+    RepresentationKind::List,
+    RepresentationKind::Link,
+];
+
+/// Why a type name couldn't be resolved to a type
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResolveError {
+    /// The name, possibly reached by following copies, is neither declared nor in the prelude
+    Undefined(TypeName),
+    /// Following copies leads back to a type already visited; the names are listed in the order visited
+    CopyCycle(Vec<TypeName>),
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Undefined(name) => write!(f, "type `{}` is undefined", name),
+            Self::CopyCycle(names) => write!(f, "copies form a cycle: {}", describe_cycle(names)),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+/// Describes a cycle of copies, e.g. "`A` = `B` = `A`"
+pub(crate) fn describe_cycle(names: &[TypeName]) -> String {
+    names
+        .iter()
+        .chain(names.first())
+        .map(|name| format!("`{}`", name))
+        .collect::<Vec<_>>()
+        .join(" = ")
+}
+
+impl Schema {
+    /// Finds the type a name ultimately refers to
+    ///
+    /// Copies are followed to the type they copy, and names from the [`PRELUDE`](super::PRELUDE) resolve to their
+    /// built-in definitions, with `Any` being a kinded union of every other prelude type.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the name, or one reached by following copies, is undefined or when copies form a cycle.
+    pub fn resolve(&self, name: &str) -> Result<Cow<'_, Type>, ResolveError> {
+        let mut visited: Vec<&TypeName> = vec![];
+        let mut seen = BTreeSet::new();
+        let mut name = name;
+
+        while let Some((declared_name, ty)) = self.types.0.get_key_value(name) {
+            if !seen.insert(declared_name) {
+                let start = visited
+                    .iter()
+                    .position(|visited| *visited == declared_name)
+                    .unwrap_or(0);
+                return Err(ResolveError::CopyCycle(
+                    visited[start..].iter().map(|&name| name.clone()).collect(),
+                ));
+            }
+            visited.push(declared_name);
+
+            match ty {
+                Type::Copy(copy) => name = copy.from_type.as_str(),
+                ty => return Ok(Cow::Borrowed(ty)),
+            }
+        }
+
+        prelude(name)
+            .map(Cow::Owned)
+            .ok_or_else(|| ResolveError::Undefined(TypeName(name.to_string())))
+    }
+
+    /// Lists every cycle of copies, each starting from its alphabetically first member
+    pub(crate) fn copy_cycles(&self) -> Vec<Vec<TypeName>> {
+        self.types
+            .iter()
+            .filter_map(|(name, _)| match self.resolve(name.as_str()) {
+                Err(ResolveError::CopyCycle(cycle)) if cycle.iter().min() == Some(name) => {
+                    Some(cycle)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Built-in definition of a type from the prelude
+fn prelude(name: &str) -> Option<Type> {
+    let any = || TypeName("Any".to_string());
+
+    Some(match name {
+        "Any" => Type::Union(TypeUnion::new(UnionRepresentation::Kinded(
+            union_representation::Kinded(
+                ANY_KIND
+                    .iter()
+                    .map(|kind| (kind.clone(), prelude_type(kind)))
+                    .collect(),
+            ),
+        ))),
+        "Bool" => Type::Bool(TypeBool),
+        "String" => Type::String(TypeString),
+        "Bytes" => Type::Bytes(TypeBytes::new(BytesRepresentation::default())),
+        "Int" => Type::Int(TypeInt),
+        "Float" => Type::Float(TypeFloat),
+        "Map" => Type::Map(TypeMap::new(TypeName("String".to_string()), any(), true)),
+        "List" => Type::List(TypeList::new(any(), true)),
+        "Link" => Type::Link(TypeLink::new(any())),
+        _ => return None,
+    })
+}
+
+/// Name of the prelude type represented as `kind`
+pub(crate) fn prelude_type(kind: &RepresentationKind) -> TypeName {
+    TypeName(
+        match kind {
+            RepresentationKind::Bool => "Bool",
+            RepresentationKind::String => "String",
+            RepresentationKind::Bytes => "Bytes",
+            RepresentationKind::Int => "Int",
+            RepresentationKind::Float => "Float",
+            RepresentationKind::Map => "Map",
+            RepresentationKind::List => "List",
+            RepresentationKind::Link => "Link",
+        }
+        .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn copies_are_followed() {
+        let schema =
+            Schema::from_dsl("type A = B\n\ntype B = C\n\ntype C [Int]\n\ntype D = String\n")
+                .unwrap();
+
+        assert_eq!(
+            schema.resolve("A").unwrap().as_ref(),
+            schema.get("C").unwrap()
+        );
+        assert_eq!(
+            schema.resolve("D").unwrap().as_ref(),
+            &Type::String(TypeString)
+        );
+        assert_eq!(
+            schema.resolve("Any").unwrap().as_ref().to_string(),
+            "union {\n  | Bool bool\n  | String string\n  | Bytes bytes\n  | Int int\n  | Float float\n  | Map map\n  | List list\n  | Link link\n} representation kinded"
+        );
+    }
+
+    #[test]
+    fn unresolvable_names_are_explained() {
+        let schema =
+            Schema::from_dsl("type A = B\n\ntype B = C\n\ntype C = B\n\ntype D = Nope\n").unwrap();
+
+        assert_eq!(
+            schema.resolve("A").unwrap_err().to_string(),
+            "copies form a cycle: `B` = `C` = `B`"
+        );
+        assert_eq!(
+            schema.resolve("D").unwrap_err(),
+            ResolveError::Undefined(TypeName("Nope".to_string()))
+        );
+        assert_eq!(
+            schema.copy_cycles(),
+            [vec![TypeName("B".to_string()), TypeName("C".to_string())]]
+        );
+    }
+}
@@ -0,0 +1,1151 @@
+//! Checking that [`Value`]s are values of the types described by a [`Schema`]
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::{
+    block_store::{self, BlockStore},
+    schema::{
+        enum_representation, struct_representation, union_representation, EnumRepresentation,
+        EnumValue, FieldName, InlineDefn, MapRepresentation, RepresentationKind, ResolveError,
+        Schema, StructRepresentation, Type, TypeEnum, TypeLink, TypeList, TypeMap, TypeName,
+        TypeStruct, TypeTerm, TypeUnion, UnionRepresentation,
+    },
+    Cid, Value,
+};
+
+/// A step from a value into one of its children
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PathSegment {
+    /// Entry of a map or field of a struct, including fields decoded from strings or lists of pairs
+    Key(String),
+    /// Element of a list
+    Index(usize),
+}
+
+/// Place within a value, displayed as a JSON pointer, e.g. `/items/3/name`
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DataPath(pub Vec<PathSegment>);
+
+impl fmt::Display for DataPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        if self.0.is_empty() {
+            return write!(f, "/");
+        }
+        for segment in &self.0 {
+            match segment {
+                PathSegment::Key(key) => {
+                    write!(f, "/{}", key.replace('~', "~0").replace('/', "~1"))?;
+                }
+                PathSegment::Index(index) => write!(f, "/{}", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a value isn't a value of the type expected where it occurs
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reason {
+    /// The value is of a kind the type can't be represented as, e.g. "int" or "null"
+    WrongKind {
+        /// Kind of the value
+        found: String,
+    },
+    /// A struct lacks a required field
+    MissingField(FieldName),
+    /// A map-represented struct or an envelope union has a key which isn't one of its fields
+    UnknownField(String),
+    /// No enum member is represented as the value, shown as it appears in the data
+    UnknownEnumMember(String),
+    /// A tuple-represented struct has the wrong number of elements
+    TupleLength {
+        /// Number of elements in the list
+        found: usize,
+        /// Number of fields in the struct
+        expected: usize,
+    },
+    /// A stringjoin-represented struct has the wrong number of segments
+    SegmentCount {
+        /// Number of segments the string splits into
+        found: usize,
+        /// Number of fields in the struct
+        expected: usize,
+    },
+    /// An entry of a stringpairs or listpairs representation isn't a key and a value
+    NotAPair {
+        /// The entry, shown as it appears in the data
+        found: String,
+    },
+    /// A union's discriminant, e.g. the kind of a kinded union's value, doesn't identify a member
+    UnknownDiscriminant {
+        /// The discriminant, shown as it appears in the data
+        found: String,
+        /// Discriminants identifying the union's members
+        allowed: Vec<String>,
+    },
+    /// A keyed union's map doesn't have exactly one entry
+    NotSingleEntry {
+        /// Number of entries in the map
+        found: usize,
+    },
+    /// An envelope or inline union's map lacks its discriminant or content key
+    MissingKey(String),
+    /// The block a link points to isn't in the block store
+    MissingBlock(Cid),
+    /// The block a link points to can't be read, doesn't match its CID, or can't be decoded
+    InvalidBlock {
+        /// CID of the block
+        cid: Cid,
+        /// What's wrong with the block
+        message: String,
+    },
+    /// The expected type can't be resolved, which only happens for schemas failing [`Schema::check`]
+    Unresolved(ResolveError),
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::WrongKind { found } => write!(f, "found {}", found),
+            Self::MissingField(field) => write!(f, "missing field `{}`", field),
+            Self::UnknownField(field) => write!(f, "unexpected field `{}`", field),
+            Self::UnknownEnumMember(found) => write!(f, "{} doesn't represent a member", found),
+            Self::TupleLength { found, expected } => write!(
+                f,
+                "tuple has {} elements, struct expects {}",
+                found, expected
+            ),
+            Self::SegmentCount { found, expected } => write!(
+                f,
+                "stringjoin segment count mismatch: found {}, struct expects {}",
+                found, expected
+            ),
+            Self::NotAPair { found } => write!(f, "{} isn't a key-value pair", found),
+            Self::UnknownDiscriminant { found, allowed } => write!(
+                f,
+                "discriminant {} isn't one of {}",
+                found,
+                if allowed.is_empty() {
+                    "no members".to_string()
+                } else {
+                    allowed.join(", ")
+                }
+            ),
+            Self::NotSingleEntry { found } => {
+                write!(f, "keyed union has {} entries, expects 1", found)
+            }
+            Self::MissingKey(key) => write!(f, "missing key {:?}", key),
+            Self::MissingBlock(cid) => write!(f, "linked block {} is missing", cid),
+            Self::InvalidBlock { cid, message } => {
+                write!(f, "linked block {} is invalid: {}", cid, message)
+            }
+            Self::Unresolved(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// A place where data doesn't conform to its schema
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /// Linked block the mismatch is within, or `None` for the data validation started from
+    pub block: Option<Cid>,
+    /// Place within the data or, if `block` is given, within that block
+    pub path: DataPath,
+    /// The schema type expected at `path`, e.g. "`Foo`" or "`{String:Int}`"
+    pub expected: String,
+    /// Why the data at `path` isn't a value of the expected type
+    pub reason: Reason,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.path)?;
+        if let Some(block) = &self.block {
+            write!(f, " in block {}", block)?;
+        }
+        write!(f, ": {}, expected {}", self.reason, self.expected)
+    }
+}
+
+impl Schema {
+    /// Checks that `data` is a value of the type named `root`, reporting every place it isn't
+    ///
+    /// Copies are followed to the types they copy, and values of types defined inline are checked like those of
+    /// named types.
+    ///
+    /// # Errors
+    ///
+    /// Fails with every mismatch between the data and the schema.
+    pub fn validate(&self, root: &str, data: &Value) -> Result<(), Vec<Mismatch>> {
+        self.validate_linked(root, data, None)
+    }
+
+    /// Checks `data` like [`Schema::validate`], also following links through `links` to check that the blocks they
+    /// point to are values of the links' expected types
+    ///
+    /// Links are followed up to the traversal depth `links` allows, so only the data itself is checked at depth 0.
+    /// Mismatches within a linked block are reported with the block's CID and their path within the block.
+    ///
+    /// # Errors
+    ///
+    /// Fails with every mismatch between the data and the schema, including linked blocks which are missing, don't
+    /// match their CID, or can't be decoded.
+    pub fn validate_linked(
+        &self,
+        root: &str,
+        data: &Value,
+        links: Option<Links<'_>>,
+    ) -> Result<(), Vec<Mismatch>> {
+        let mut validator = Validator::new(self, vec![]);
+        validator.links = links;
+        validator.named(root, data);
+
+        validator.finish()
+    }
+
+    /// Checks that the block `root_block` identifies is a value of the type named `root`, following links from it
+    /// like [`Schema::validate_linked`]
+    ///
+    /// # Errors
+    ///
+    /// Fails with every mismatch between the blocks and the schema, including blocks which are missing, don't match
+    /// their CID, or can't be decoded.
+    pub fn validate_block(
+        &self,
+        root: &str,
+        root_block: &Cid,
+        links: Links<'_>,
+    ) -> Result<(), Vec<Mismatch>> {
+        let mut validator = Validator::new(self, vec![]);
+        validator.follow(root, &root, root_block, links);
+        validator.finish()
+    }
+}
+
+/// Where validation fetches the blocks links point to, and how many links deep it follows them
+#[derive(Clone, Copy)]
+pub struct Links<'a> {
+    /// Store holding the blocks links point to
+    pub store: &'a dyn BlockStore,
+    /// How many links deep to follow links, beyond which linked blocks aren't checked
+    pub max_depth: usize,
+}
+
+struct Validator<'a> {
+    schema: &'a Schema,
+    /// Linked block being checked, if not the data validation started from
+    block: Option<Cid>,
+    path: Vec<PathSegment>,
+    mismatches: Vec<Mismatch>,
+    links: Option<Links<'a>>,
+    /// Linked blocks already checked against each type, with the link depth that was left to follow within them
+    checked: BTreeMap<(Cid, String), usize>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(schema: &'a Schema, path: Vec<PathSegment>) -> Self {
+        Self {
+            schema,
+            block: None,
+            path,
+            mismatches: vec![],
+            links: None,
+            checked: BTreeMap::new(),
+        }
+    }
+
+    fn finish(self) -> Result<(), Vec<Mismatch>> {
+        if self.mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(self.mismatches)
+        }
+    }
+
+    fn mismatch(&mut self, expected: &dyn fmt::Display, reason: Reason) {
+        self.mismatches.push(Mismatch {
+            block: self.block.clone(),
+            path: DataPath(self.path.clone()),
+            expected: format!("`{}`", expected),
+            reason,
+        });
+    }
+
+    /// Checks a child of the current value
+    fn at(&mut self, segment: PathSegment, check: impl FnOnce(&mut Self)) {
+        self.path.push(segment);
+        check(self);
+        self.path.pop();
+    }
+
+    fn term(&mut self, term: &TypeTerm, nullable: bool, value: &Value) {
+        if nullable && *value == Value::Null {
+            return;
+        }
+        match term {
+            TypeTerm::TypeName(name) => self.named(name.as_str(), value),
+            TypeTerm::InlineDefn(inline) => match inline.as_ref() {
+                InlineDefn::Map(map) => self.map(map, term, value),
+                InlineDefn::List(list) => self.list(list, term, value),
+            },
+        }
+    }
+
+    fn named(&mut self, name: &str, value: &Value) {
+        let schema = self.schema;
+        match schema.resolve(name) {
+            Ok(ty) => self.ty(&ty, &name, value),
+            Err(err) => self.mismatch(&name, Reason::Unresolved(err)),
+        }
+    }
+
+    fn ty(&mut self, ty: &Type, expected: &dyn fmt::Display, value: &Value) {
+        let kind = match ty {
+            Type::Bool(_) => RepresentationKind::Bool,
+            Type::String(_) => RepresentationKind::String,
+            Type::Bytes(_) => RepresentationKind::Bytes,
+            Type::Int(_) => RepresentationKind::Int,
+            Type::Float(_) => RepresentationKind::Float,
+            Type::Link(link) => return self.link(link, expected, value),
+            Type::Map(map) => return self.map(map, expected, value),
+            Type::List(list) => return self.list(list, expected, value),
+            Type::Struct(s) => return self.r#struct(s, expected, value),
+            Type::Enum(e) => return self.r#enum(e, expected, value),
+            Type::Union(u) => return self.union(u, expected, value),
+            Type::Copy(copy) => return self.named(copy.from_type().as_str(), value),
+        };
+        if value.kind() != Some(kind) {
+            self.wrong_kind(expected, value);
+        }
+    }
+
+    /// Checks a link, and the block it points to if links are followed and the depth allows
+    fn link(&mut self, link: &TypeLink, expected: &dyn fmt::Display, value: &Value) {
+        match (value, self.links) {
+            (Value::Link(cid), Some(links)) if links.max_depth > 0 => {
+                let within = Links {
+                    max_depth: links.max_depth - 1,
+                    ..links
+                };
+                self.follow(link.expected_type(), expected, cid, within);
+            }
+            (Value::Link(_), _) => {}
+            _ => self.wrong_kind(expected, value),
+        }
+    }
+
+    /// Checks that the block `cid` identifies is a value of the type `name`, following links within it through
+    /// `within`
+    ///
+    /// Blocks reached again are skipped unless more links can be followed within them than before, so blocks shared
+    /// by several paths are only checked, and their mismatches only reported, once.
+    fn follow(&mut self, name: &str, expected: &dyn fmt::Display, cid: &Cid, within: Links<'a>) {
+        let key = (cid.clone(), name.to_string());
+        if self
+            .checked
+            .get(&key)
+            .is_some_and(|&depth| depth >= within.max_depth)
+        {
+            return;
+        }
+        self.checked.insert(key, within.max_depth);
+
+        match block_store::load(within.store, cid) {
+            Ok(Some(block)) => {
+                let links = self.links.replace(within);
+                let outer = self.block.replace(cid.clone());
+                let path = std::mem::take(&mut self.path);
+                self.named(name, &block);
+                self.path = path;
+                self.block = outer;
+                self.links = links;
+            }
+            Ok(None) => self.mismatch(expected, Reason::MissingBlock(cid.clone())),
+            Err(err) => self.mismatch(
+                expected,
+                Reason::InvalidBlock {
+                    cid: cid.clone(),
+                    message: err.to_string(),
+                },
+            ),
+        }
+    }
+
+    fn wrong_kind(&mut self, expected: &dyn fmt::Display, value: &Value) {
+        self.mismatch(
+            expected,
+            Reason::WrongKind {
+                found: value.kind_name(),
+            },
+        );
+    }
+
+    /// Decodes a map from its representation and checks its entries
+    fn map(&mut self, map: &TypeMap, expected: &dyn fmt::Display, value: &Value) {
+        let entries = match (map.representation(), value) {
+            (MapRepresentation::Map(_), Value::Map(entries)) => Cow::Borrowed(entries),
+            (MapRepresentation::StringPairs(pairs), Value::String(text)) => {
+                Cow::Owned(self.string_pairs(text, &pairs.inner_delim, &pairs.entry_delim))
+            }
+            (MapRepresentation::ListPairs(_), Value::List(items)) => {
+                Cow::Owned(self.list_pairs(items))
+            }
+            // advanced layouts can't be interpreted without their implementation
+            (MapRepresentation::Advanced(_), _) => return,
+            _ => return self.wrong_kind(expected, value),
+        };
+        for (key, value) in entries.iter() {
+            self.at(PathSegment::Key(key.clone()), |this| {
+                this.named(map.key_type().as_str(), &Value::String(key.clone()));
+                this.term(map.value_type(), map.value_nullable(), value);
+            });
+        }
+    }
+
+    fn list(&mut self, list: &TypeList, expected: &dyn fmt::Display, value: &Value) {
+        if let Value::List(items) = value {
+            for (index, item) in items.iter().enumerate() {
+                self.at(PathSegment::Index(index), |this| {
+                    this.term(list.value_type(), list.value_nullable(), item);
+                });
+            }
+        } else {
+            self.wrong_kind(expected, value);
+        }
+    }
+
+    /// Decodes a struct from its representation and checks its fields
+    fn r#struct(&mut self, s: &TypeStruct, expected: &dyn fmt::Display, value: &Value) {
+        match (s.representation(), value) {
+            (StructRepresentation::Map(details), Value::Map(entries)) => {
+                self.struct_fields(s, Some(details), expected, entries);
+            }
+            (StructRepresentation::Tuple(tuple), Value::List(items)) => {
+                let field_order = tuple
+                    .field_order
+                    .clone()
+                    .unwrap_or_else(|| s.fields().map(|(name, _)| name.clone()).collect());
+                self.tuple(s, &field_order, expected, items);
+            }
+            (StructRepresentation::StringPairs(pairs), Value::String(text)) => {
+                let entries = self.string_pairs(text, &pairs.inner_delim, &pairs.entry_delim);
+                self.struct_fields(s, None, expected, &entries);
+            }
+            (StructRepresentation::StringJoin(join), Value::String(text)) => {
+                self.string_join(s, join, expected, text);
+            }
+            (StructRepresentation::ListPairs(_), Value::List(items)) => {
+                let entries = self.list_pairs(items);
+                self.struct_fields(s, None, expected, &entries);
+            }
+            _ => self.wrong_kind(expected, value),
+        }
+    }
+
+    /// Checks the fields of a struct in map form, where `details` may rename fields or give them implicit values
+    fn struct_fields(
+        &mut self,
+        s: &TypeStruct,
+        details: Option<&struct_representation::Map>,
+        expected: &dyn fmt::Display,
+        entries: &BTreeMap<String, Value>,
+    ) {
+        let field_details = |name: &FieldName| details.and_then(|d| d.fields.get(name));
+        let key = |name: &FieldName| {
+            field_details(name)
+                .and_then(|d| d.rename.clone())
+                .unwrap_or_else(|| name.to_string())
+        };
+
+        for (name, field) in s.fields() {
+            let key = key(name);
+            match entries.get(&key) {
+                Some(value) => self.at(PathSegment::Key(key), |this| {
+                    this.term(field.type_term(), field.nullable(), value);
+                }),
+                None if field.optional() => {}
+                None if field_details(name).is_some_and(|d| d.implicit.is_some()) => {}
+                None => self.mismatch(expected, Reason::MissingField(name.clone())),
+            }
+        }
+
+        let keys: BTreeSet<String> = s.fields().map(|(name, _)| key(name)).collect();
+        for unknown in entries.keys().filter(|k| !keys.contains(*k)) {
+            self.mismatch(expected, Reason::UnknownField(unknown.clone()));
+        }
+    }
+
+    fn tuple(
+        &mut self,
+        s: &TypeStruct,
+        field_order: &[FieldName],
+        expected: &dyn fmt::Display,
+        items: &[Value],
+    ) {
+        if items.len() != field_order.len() {
+            return self.mismatch(
+                expected,
+                Reason::TupleLength {
+                    found: items.len(),
+                    expected: field_order.len(),
+                },
+            );
+        }
+        for (index, (name, item)) in field_order.iter().zip(items).enumerate() {
+            if let Some(field) = s.field(name.as_str()) {
+                self.at(PathSegment::Index(index), |this| {
+                    this.term(field.type_term(), field.nullable(), item);
+                });
+            }
+        }
+    }
+
+    fn string_join(
+        &mut self,
+        s: &TypeStruct,
+        join: &struct_representation::StringJoin,
+        expected: &dyn fmt::Display,
+        text: &str,
+    ) {
+        let segments: Vec<&str> = text.split(join.join.as_str()).collect();
+        if segments.len() != join.field_order.len() {
+            return self.mismatch(
+                expected,
+                Reason::SegmentCount {
+                    found: segments.len(),
+                    expected: join.field_order.len(),
+                },
+            );
+        }
+        for (name, segment) in join.field_order.iter().zip(segments) {
+            if let Some(field) = s.field(name.as_str()) {
+                self.at(PathSegment::Key(name.to_string()), |this| {
+                    this.term(
+                        field.type_term(),
+                        false,
+                        &Value::String(segment.to_string()),
+                    );
+                });
+            }
+        }
+    }
+
+    /// Decodes entries joined by `entry_delim`, each a key and value joined by `inner_delim`
+    fn string_pairs(
+        &mut self,
+        text: &str,
+        inner_delim: &str,
+        entry_delim: &str,
+    ) -> BTreeMap<String, Value> {
+        let mut entries = BTreeMap::new();
+        for (index, entry) in text
+            .split(entry_delim)
+            .filter(|e| !e.is_empty())
+            .enumerate()
+        {
+            if let Some((key, value)) = entry.split_once(inner_delim) {
+                entries.insert(key.to_string(), Value::String(value.to_string()));
+            } else {
+                self.at(PathSegment::Index(index), |this| {
+                    this.mismatch(
+                        &"stringpairs entry",
+                        Reason::NotAPair {
+                            found: format!("{:?}", entry),
+                        },
+                    );
+                });
+            }
+        }
+        entries
+    }
+
+    /// Decodes a list of `[key, value]` lists
+    fn list_pairs(&mut self, items: &[Value]) -> BTreeMap<String, Value> {
+        let mut entries = BTreeMap::new();
+        for (index, item) in items.iter().enumerate() {
+            let found = match item {
+                Value::List(pair) => match pair.as_slice() {
+                    [Value::String(key), value] => {
+                        entries.insert(key.clone(), value.clone());
+                        continue;
+                    }
+                    [key, _] => format!("pair with {} key", key.kind_name()),
+                    _ => format!("list of length {}", pair.len()),
+                },
+                _ => item.kind_name(),
+            };
+            self.at(PathSegment::Index(index), |this| {
+                this.mismatch(&"[key, value]", Reason::NotAPair { found });
+            });
+        }
+        entries
+    }
+
+    /// Decodes an enum member from its string or int representation
+    fn r#enum(&mut self, e: &TypeEnum, expected: &dyn fmt::Display, value: &Value) {
+        let found = match (e.representation(), value) {
+            (EnumRepresentation::String(enum_representation::String(table)), Value::String(s)) => {
+                let represents = |member: &EnumValue| {
+                    table.get(member).map_or(member.as_str(), String::as_str) == s
+                };
+                if e.members().any(represents) {
+                    return;
+                }
+                format!("{:?}", s)
+            }
+            (EnumRepresentation::Int(enum_representation::Int(table)), Value::Int(i)) => {
+                if e.members().any(|member| table.get(member) == Some(i)) {
+                    return;
+                }
+                i.to_string()
+            }
+            _ => return self.wrong_kind(expected, value),
+        };
+        self.mismatch(expected, Reason::UnknownEnumMember(found));
+    }
+
+    /// Finds the member of a union identified by its discriminant and checks the value against it
+    fn union(&mut self, u: &TypeUnion, expected: &dyn fmt::Display, value: &Value) {
+        match (u.representation(), value) {
+            (UnionRepresentation::Kinded(union_representation::Kinded(table)), _) => {
+                let member = value.kind().and_then(|kind| table.get(&kind));
+                if let Some(member) = member {
+                    self.named(member.as_str(), value);
+                } else {
+                    let allowed = table.keys().map(ToString::to_string).collect();
+                    self.unknown_discriminant(expected, value.kind_name(), allowed);
+                }
+            }
+            (
+                UnionRepresentation::Keyed(union_representation::Keyed(table)),
+                Value::Map(entries),
+            ) => {
+                if entries.len() != 1 {
+                    return self.mismatch(
+                        expected,
+                        Reason::NotSingleEntry {
+                            found: entries.len(),
+                        },
+                    );
+                }
+                for (key, content) in entries {
+                    if let Some(member) = self.discriminated(expected, table, key) {
+                        self.at(PathSegment::Key(key.clone()), |this| {
+                            this.named(member.as_str(), content);
+                        });
+                    }
+                }
+            }
+            (UnionRepresentation::Envelope(envelope), Value::Map(entries)) => {
+                self.envelope(envelope, expected, entries);
+            }
+            (UnionRepresentation::Inline(inline), Value::Map(entries)) => {
+                let mut content = entries.clone();
+                let discriminant = content.remove(&inline.discriminant_key);
+                if let Some(member) = self.discriminant(
+                    expected,
+                    &inline.discriminant_key,
+                    discriminant.as_ref(),
+                    &inline.discriminant_table,
+                ) {
+                    self.named(member.as_str(), &Value::Map(content));
+                }
+            }
+            (UnionRepresentation::BytePrefix(byte_prefix), Value::Bytes(bytes)) => {
+                let table = &byte_prefix.discriminant_table;
+                let member = bytes.split_first().and_then(|(first, rest)| {
+                    let member = table.iter().find(|(_, byte)| *byte == first)?.0;
+                    Some((member, rest))
+                });
+                if let Some((member, rest)) = member {
+                    self.named(member.as_str(), &Value::Bytes(rest.to_vec()));
+                } else {
+                    let found = bytes
+                        .first()
+                        .map_or_else(|| "nothing".to_string(), ToString::to_string);
+                    let allowed = table.values().map(ToString::to_string).collect();
+                    self.unknown_discriminant(expected, found, allowed);
+                }
+            }
+            _ => self.wrong_kind(expected, value),
+        }
+    }
+
+    fn envelope(
+        &mut self,
+        envelope: &union_representation::Envelope,
+        expected: &dyn fmt::Display,
+        entries: &BTreeMap<String, Value>,
+    ) {
+        let member = self.discriminant(
+            expected,
+            &envelope.discriminant_key,
+            entries.get(&envelope.discriminant_key),
+            &envelope.discriminant_table,
+        );
+        match (member, entries.get(&envelope.content_key)) {
+            (Some(member), Some(content)) => {
+                self.at(PathSegment::Key(envelope.content_key.clone()), |this| {
+                    this.named(member.as_str(), content);
+                });
+            }
+            (_, None) => self.mismatch(expected, Reason::MissingKey(envelope.content_key.clone())),
+            (None, Some(_)) => {}
+        }
+        for key in entries.keys() {
+            if *key != envelope.discriminant_key && *key != envelope.content_key {
+                self.mismatch(expected, Reason::UnknownField(key.clone()));
+            }
+        }
+    }
+
+    /// Reads the discriminant found under `key`, which must be one of the strings in `table`
+    fn discriminant<'t>(
+        &mut self,
+        expected: &dyn fmt::Display,
+        key: &str,
+        discriminant: Option<&Value>,
+        table: &'t BTreeMap<String, TypeName>,
+    ) -> Option<&'t TypeName> {
+        match discriminant {
+            Some(Value::String(discriminant)) => self.discriminated(expected, table, discriminant),
+            Some(value) => {
+                self.at(PathSegment::Key(key.to_string()), |this| {
+                    this.wrong_kind(&"String", value);
+                });
+                None
+            }
+            None => {
+                self.mismatch(expected, Reason::MissingKey(key.to_string()));
+                None
+            }
+        }
+    }
+
+    /// Looks up the member a string discriminant identifies
+    fn discriminated<'t>(
+        &mut self,
+        expected: &dyn fmt::Display,
+        table: &'t BTreeMap<String, TypeName>,
+        discriminant: &str,
+    ) -> Option<&'t TypeName> {
+        let member = table.get(discriminant);
+        if member.is_none() {
+            let allowed = table.keys().map(|key| format!("{:?}", key)).collect();
+            self.unknown_discriminant(expected, format!("{:?}", discriminant), allowed);
+        }
+        member
+    }
+
+    fn unknown_discriminant(
+        &mut self,
+        expected: &dyn fmt::Display,
+        found: String,
+        allowed: Vec<String>,
+    ) {
+        self.mismatch(expected, Reason::UnknownDiscriminant { found, allowed });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::TryFrom;
+
+    use crate::schema::SchemaBuilder;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn problems(schema: &str, root: &str, data: serde_json::Value) -> Vec<String> {
+        validate(&Schema::from_dsl(schema).unwrap(), root, data)
+    }
+
+    fn validate(schema: &Schema, root: &str, data: serde_json::Value) -> Vec<String> {
+        schema
+            .validate(root, &Value::try_from(data).unwrap())
+            .err()
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    const SCHEMA: &str = "type Order struct {\n  items [Item]\n  note optional nullable String\n  tags Tags\n  sizes [Size]\n}\n\ntype Tags nullable {String:Int}\n\ntype Item struct {\n  name Name\n  size optional Size\n}\n\ntype Name = String\n\ntype Size enum {\n  | small\n  | large\n}\n";
+
+    #[test]
+    fn conforming_data_is_valid() {
+        assert_eq!(
+            problems(
+                SCHEMA,
+                "Order",
+                json!({
+                    "items": [{ "name": "a" }, { "name": "b", "size": "large" }],
+                    "note": null,
+                    "tags": { "x": 1, "y": null },
+                    "sizes": ["small"],
+                })
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            problems(SCHEMA, "Any", json!([1, {"a": null}])),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn every_mismatch_is_reported_with_its_path() {
+        assert_eq!(
+            problems(
+                SCHEMA,
+                "Order",
+                json!({
+                    "items": [{ "name": "a" }, { "name": 3, "size": "medium" }, {}],
+                    "tags": { "a/b": 1.5 },
+                    "sizes": [null],
+                    "extra": true,
+                })
+            ),
+            [
+                "/items/1/name: found int, expected `Name`",
+                "/items/1/size: \"medium\" doesn't represent a member, expected `Size`",
+                "/items/2: missing field `name`, expected `Item`",
+                "/sizes/0: found null, expected `Size`",
+                "/tags/a~1b: found float, expected `Int`",
+                "/: unexpected field `extra`, expected `Order`",
+            ]
+        );
+        assert_eq!(
+            problems(SCHEMA, "Name", json!(null)),
+            ["/: found null, expected `Name`"]
+        );
+        assert_eq!(
+            problems(SCHEMA, "Missing", json!(null)),
+            ["/: type `Missing` is undefined, expected `Missing`"]
+        );
+    }
+
+    #[test]
+    fn structs_are_decoded_from_their_representations() {
+        let schema = "type T struct {\n  a Int\n  b nullable String\n} representation tuple\n\ntype O struct {\n  a Int\n  b String\n} representation tuple {\n  fieldOrder [\"b\", \"a\"]\n}\n\ntype P struct {\n  a String\n  b Name\n} representation stringpairs {\n  innerDelim \"=\"\n  entryDelim \",\"\n}\n\ntype J struct {\n  a String\n  b Name\n} representation stringjoin {\n  join \":\"\n}\n\ntype L struct {\n  a Int\n} representation listpairs\n\ntype I struct {\n  a Int (implicit 1)\n}\n\ntype Name enum {\n  | x\n}\n";
+
+        assert_eq!(
+            problems(schema, "T", json!([1, null])),
+            Vec::<String>::new()
+        );
+        assert_eq!(problems(schema, "O", json!(["s", 1])), Vec::<String>::new());
+        assert_eq!(
+            problems(schema, "P", json!("a=1,b=x")),
+            Vec::<String>::new()
+        );
+        assert_eq!(problems(schema, "J", json!("1:x")), Vec::<String>::new());
+        assert_eq!(
+            problems(schema, "L", json!([["a", 1]])),
+            Vec::<String>::new()
+        );
+        assert_eq!(problems(schema, "I", json!({})), Vec::<String>::new());
+
+        assert_eq!(
+            problems(schema, "T", json!([1, "b", 3, 4])),
+            ["/: tuple has 4 elements, struct expects 2, expected `T`"]
+        );
+        assert_eq!(
+            problems(schema, "O", json!([1, "s"])),
+            [
+                "/0: found int, expected `String`",
+                "/1: found string, expected `Int`",
+            ]
+        );
+        assert_eq!(
+            problems(schema, "P", json!("a=1,b=y,c")),
+            [
+                "/2: \"c\" isn't a key-value pair, expected `stringpairs entry`",
+                "/b: \"y\" doesn't represent a member, expected `Name`",
+            ]
+        );
+        assert_eq!(
+            problems(schema, "J", json!("1:x:2")),
+            ["/: stringjoin segment count mismatch: found 3, struct expects 2, expected `J`"]
+        );
+        assert_eq!(
+            problems(schema, "L", json!([["a"], [1, 2]])),
+            [
+                "/0: list of length 1 isn't a key-value pair, expected `[key, value]`",
+                "/1: pair with int key isn't a key-value pair, expected `[key, value]`",
+                "/: missing field `a`, expected `L`",
+            ]
+        );
+        assert_eq!(
+            problems(schema, "T", json!({ "a": 1 })),
+            ["/: found map, expected `T`"]
+        );
+    }
+
+    #[test]
+    fn renamed_fields_are_read_from_their_keys() {
+        let schema = Schema::from_json(
+            r#"{"types": {"R": {"kind": "struct", "fields": {"a": {"type": "Int"}}, "representation": {"map": {"fields": {"a": {"rename": "x"}}}}}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            validate(&schema, "R", json!({ "x": 1 })),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            validate(&schema, "R", json!({ "a": 1 })),
+            [
+                "/: missing field `a`, expected `R`",
+                "/: unexpected field `a`, expected `R`",
+            ]
+        );
+    }
+
+    #[test]
+    fn unions_are_decoded_from_their_representations() {
+        let schema = "type K union {\n  | String string\n  | C map\n} representation kinded\n\ntype Y union {\n  | Int \"i\"\n  | C \"c\"\n} representation keyed\n\ntype E union {\n  | Int \"i\"\n  | C \"c\"\n} representation envelope {\n  discriminantKey \"tag\"\n  contentKey \"content\"\n}\n\ntype I union {\n  | C \"c\"\n} representation inline {\n  discriminantKey \"tag\"\n}\n\ntype B union {\n  | Bytes 1\n  | D 2\n} representation byteprefix\n\ntype C struct {\n  x Int\n}\n\ntype D bytes\n";
+
+        assert_eq!(problems(schema, "K", json!("s")), Vec::<String>::new());
+        assert_eq!(
+            problems(schema, "K", json!({ "x": 1 })),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            problems(schema, "Y", json!({ "c": { "x": 1 } })),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            problems(schema, "E", json!({ "tag": "i", "content": 1 })),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            problems(schema, "I", json!({ "tag": "c", "x": 1 })),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            problems(schema, "B", json!({ "/": { "bytes": "AgM" } })),
+            Vec::<String>::new()
+        );
+
+        assert_eq!(
+            problems(schema, "K", json!(1)),
+            ["/: discriminant int isn't one of string, map, expected `K`"]
+        );
+        assert_eq!(
+            problems(schema, "Y", json!({ "i": 1, "c": {} })),
+            ["/: keyed union has 2 entries, expects 1, expected `Y`"]
+        );
+        assert_eq!(
+            problems(schema, "Y", json!({ "x": 1 })),
+            ["/: discriminant \"x\" isn't one of \"c\", \"i\", expected `Y`"]
+        );
+        assert_eq!(
+            problems(schema, "E", json!({ "tag": 1, "extra": 1 })),
+            [
+                "/tag: found int, expected `String`",
+                "/: missing key \"content\", expected `E`",
+                "/: unexpected field `extra`, expected `E`",
+            ]
+        );
+        assert_eq!(
+            problems(schema, "E", json!({ "tag": "c", "content": { "x": "1" } })),
+            ["/content/x: found string, expected `Int`"]
+        );
+        assert_eq!(
+            problems(schema, "I", json!({ "x": 1 })),
+            ["/: missing key \"tag\", expected `I`"]
+        );
+        assert_eq!(
+            problems(schema, "B", json!({ "/": { "bytes": "Aw" } })),
+            ["/: discriminant 3 isn't one of 1, 2, expected `B`"]
+        );
+        assert_eq!(
+            problems(schema, "B", json!("s")),
+            ["/: found string, expected `B`"]
+        );
+    }
+
+    #[test]
+    fn maps_are_decoded_from_their_representations() {
+        let schema = "type P {String:E} representation stringpairs {\n  innerDelim \"=\"\n  entryDelim \"&\"\n}\n\ntype L {String:Bool} representation listpairs\n\ntype E enum {\n  | x\n}\n";
+
+        assert_eq!(
+            problems(schema, "P", json!("a=x&b=x")),
+            Vec::<String>::new()
+        );
+        assert_eq!(problems(schema, "P", json!("")), Vec::<String>::new());
+        assert_eq!(
+            problems(schema, "L", json!([["a", true]])),
+            Vec::<String>::new()
+        );
+
+        assert_eq!(
+            problems(schema, "P", json!("a=x&b")),
+            ["/1: \"b\" isn't a key-value pair, expected `stringpairs entry`"]
+        );
+        assert_eq!(
+            problems(schema, "P", json!("a=y")),
+            ["/a: \"y\" doesn't represent a member, expected `E`"]
+        );
+        assert_eq!(
+            problems(schema, "L", json!([["a", 1]])),
+            ["/a: found int, expected `Bool`"]
+        );
+        assert_eq!(
+            problems(schema, "L", json!({ "a": true })),
+            ["/: found map, expected `L`"]
+        );
+    }
+
+    #[test]
+    fn enums_are_decoded_from_their_representations() {
+        let members = || vec!["a".parse().unwrap(), "b".parse().unwrap()];
+        let mut builder = SchemaBuilder::new();
+        builder
+            .add_type("S".parse().unwrap(), TypeEnum::new(members()))
+            .unwrap()
+            .set_representation(
+                "S",
+                EnumRepresentation::String(enum_representation::String(
+                    vec![("a".parse().unwrap(), "A".to_string())]
+                        .into_iter()
+                        .collect(),
+                )),
+            )
+            .unwrap()
+            .add_type("I".parse().unwrap(), TypeEnum::new(members()))
+            .unwrap()
+            .set_representation(
+                "I",
+                EnumRepresentation::Int(enum_representation::Int(
+                    vec![("a".parse().unwrap(), 0), ("b".parse().unwrap(), 1)]
+                        .into_iter()
+                        .collect(),
+                )),
+            )
+            .unwrap();
+        let schema = builder.build().unwrap();
+
+        assert_eq!(validate(&schema, "S", json!("A")), Vec::<String>::new());
+        assert_eq!(validate(&schema, "S", json!("b")), Vec::<String>::new());
+        assert_eq!(validate(&schema, "I", json!(1)), Vec::<String>::new());
+
+        assert_eq!(
+            validate(&schema, "S", json!("a")),
+            ["/: \"a\" doesn't represent a member, expected `S`"]
+        );
+        assert_eq!(
+            validate(&schema, "I", json!(2)),
+            ["/: 2 doesn't represent a member, expected `I`"]
+        );
+        assert_eq!(
+            validate(&schema, "I", json!("a")),
+            ["/: found string, expected `I`"]
+        );
+    }
+
+    #[test]
+    fn links_are_followed_through_a_block_store_to_the_allowed_depth() {
+        let schema = Schema::from_dsl(
+            "type Root struct {\n  next NodeLink\n}\n\ntype NodeLink &Node\n\ntype Node struct {\n  value Int\n  child nullable NodeLink\n}\n",
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::block_store::DirectoryStore::new(dir.path());
+        let leaf = store
+            .put(crate::cid::DAG_JSON, br#"{"value": "x", "child": null}"#)
+            .unwrap();
+        let node = store
+            .put(
+                crate::cid::DAG_JSON,
+                format!(r#"{{"value": 1, "child": {{"/": "{}"}}}}"#, leaf).as_bytes(),
+            )
+            .unwrap();
+        let missing = Cid::new_v1(crate::cid::DAG_JSON, b"{}");
+
+        let check = |next: &Cid, max_depth| -> Vec<String> {
+            let data = Value::try_from(json!({ "next": { "/": next.to_string() } })).unwrap();
+            schema
+                .validate_linked(
+                    "Root",
+                    &data,
+                    Some(Links {
+                        store: &store,
+                        max_depth,
+                    }),
+                )
+                .err()
+                .unwrap_or_default()
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        };
+
+        assert_eq!(check(&node, 0), Vec::<String>::new());
+        assert_eq!(check(&node, 1), Vec::<String>::new());
+        assert_eq!(
+            check(&node, 2),
+            [format!(
+                "/value in block {}: found string, expected `Int`",
+                leaf
+            )]
+        );
+        assert_eq!(
+            check(&missing, 1),
+            [format!(
+                "/next: linked block {} is missing, expected `NodeLink`",
+                missing
+            )]
+        );
+    }
+
+    #[test]
+    fn blocks_shared_by_several_links_are_checked_once() {
+        let schema = Schema::from_dsl(
+            "type Node struct {\n  value Int\n  left nullable NodeLink\n  right nullable NodeLink\n}\n\ntype NodeLink &Node\n",
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::block_store::DirectoryStore::new(dir.path());
+        let leaf = store
+            .put(
+                crate::cid::DAG_JSON,
+                br#"{"value": "x", "left": null, "right": null}"#,
+            )
+            .unwrap();
+        // each level links to the one below twice, so there are 2^64 paths to the leaf
+        let mut node = leaf.clone();
+        for _ in 0..64 {
+            let link = format!(r#"{{"/": "{}"}}"#, node);
+            let block = format!(r#"{{"value": 1, "left": {0}, "right": {0}}}"#, link);
+            node = store.put(crate::cid::DAG_JSON, block.as_bytes()).unwrap();
+        }
+
+        let mismatches = schema
+            .validate_block(
+                "Node",
+                &node,
+                Links {
+                    store: &store,
+                    max_depth: 64,
+                },
+            )
+            .unwrap_err();
+        assert_eq!(
+            mismatches
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [format!(
+                "/value in block {}: found string, expected `Int`",
+                leaf
+            )]
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Values of the [IPLD Data Model](https://specs.ipld.io/data-model-layer/data-model.html), which schemas describe
+
+use std::{cmp::Ordering, collections::BTreeMap, convert::TryFrom, fmt};
+
+use crate::{
+    cid::{Cid, CidError},
+    schema::RepresentationKind,
+};
+
+/// A value of any kind in the IPLD Data Model
+///
+/// Ints and floats are distinct kinds, so `Int(1)` and `Float(1.0)` aren't equal. Floats compare by their total
+/// order, making equality reflexive even though the Data Model doesn't admit NaN or infinities anyway. Values of
+/// different kinds are ordered by kind, in the order the variants are declared.
+#[derive(Clone, Debug)]
+pub enum Value {
+    /// The absence of a value
+    Null,
+    /// `true` or `false`
+    Bool(bool),
+    /// Integer, limited to 64 bits like most implementations
+    Int(i64),
+    /// Floating-point number, which the Data Model requires to be finite
+    Float(f64),
+    /// UTF-8 text
+    String(String),
+    /// Arbitrary bytes
+    Bytes(Vec<u8>),
+    /// Values in order
+    List(Vec<Value>),
+    /// Entries ordered by key, since the Data Model gives no significance to their order
+    Map(BTreeMap<String, Value>),
+    /// Link to another block, identified by its CID
+    Link(Cid),
+}
+
+impl Value {
+    /// Representation kind of the value, or `None` for null, which no representation strategy produces on its own
+    #[must_use]
+    pub fn kind(&self) -> Option<RepresentationKind> {
+        Some(match self {
+            Self::Null => return None,
+            Self::Bool(_) => RepresentationKind::Bool,
+            Self::Int(_) => RepresentationKind::Int,
+            Self::Float(_) => RepresentationKind::Float,
+            Self::String(_) => RepresentationKind::String,
+            Self::Bytes(_) => RepresentationKind::Bytes,
+            Self::List(_) => RepresentationKind::List,
+            Self::Map(_) => RepresentationKind::Map,
+            Self::Link(_) => RepresentationKind::Link,
+        })
+    }
+
+    /// Name of the value's kind, e.g. "null" or "map"
+    #[must_use]
+    pub fn kind_name(&self) -> String {
+        self.kind()
+            .map_or_else(|| "null".to_string(), |kind| kind.to_string())
+    }
+
+    /// CIDs of every link within the value, in depth-first order
+    #[must_use]
+    pub fn links(&self) -> Vec<&Cid> {
+        match self {
+            Self::Link(cid) => vec![cid],
+            Self::List(items) => items.iter().flat_map(Self::links).collect(),
+            Self::Map(entries) => entries.values().flat_map(Self::links).collect(),
+            _ => vec![],
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Null => 0,
+            Self::Bool(_) => 1,
+            Self::Int(_) => 2,
+            Self::Float(_) => 3,
+            Self::String(_) => 4,
+            Self::Bytes(_) => 5,
+            Self::List(_) => 6,
+            Self::Map(_) => 7,
+            Self::Link(_) => 8,
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Null, Self::Null) => Ordering::Equal,
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::Int(a), Self::Int(b)) => a.cmp(b),
+            (Self::Float(a), Self::Float(b)) => a.total_cmp(b),
+            (Self::String(a), Self::String(b)) => a.cmp(b),
+            (Self::Bytes(a), Self::Bytes(b)) => a.cmp(b),
+            (Self::List(a), Self::List(b)) => a.cmp(b),
+            (Self::Map(a), Self::Map(b)) => a.cmp(b),
+            (Self::Link(a), Self::Link(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+/// Why JSON couldn't be converted to or from a [`Value`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonError {
+    /// A JSON number is an integer outside the range of [`Value::Int`]
+    IntOutOfRange(serde_json::Number),
+    /// A float is NaN or infinite, which JSON can't express
+    NonFiniteFloat(f64),
+    /// A `{"/": {"bytes": ...}}` object doesn't contain valid unpadded base64
+    InvalidBytes(String),
+    /// A `{"/": ...}` object's string isn't a CID
+    InvalidLink(String, CidError),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::IntOutOfRange(n) => write!(f, "integer {} is out of range", n),
+            Self::NonFiniteFloat(x) => write!(f, "float {} can't be expressed in JSON", x),
+            Self::InvalidBytes(s) => write!(f, "bytes {:?} aren't valid base64", s),
+            Self::InvalidLink(s, err) => write!(f, "link {:?} isn't a valid CID: {}", s, err),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// Converts JSON following the DAG-JSON conventions: `{"/": "<cid>"}` is a link and `{"/": {"bytes": "<base64>"}}`
+/// is bytes
+impl TryFrom<serde_json::Value> for Value {
+    type Error = JsonError;
+
+    fn try_from(json: serde_json::Value) -> Result<Self, Self::Error> {
+        use serde_json::Value as J;
+
+        Ok(match json {
+            J::Null => Self::Null,
+            J::Bool(b) => Self::Bool(b),
+            J::Number(n) => match (n.as_i64(), n.is_f64()) {
+                (Some(i), _) => Self::Int(i),
+                (None, true) => Self::Float(n.as_f64().unwrap_or_default()),
+                (None, false) => return Err(JsonError::IntOutOfRange(n)),
+            },
+            J::String(s) => Self::String(s),
+            J::Array(items) => Self::List(
+                items
+                    .into_iter()
+                    .map(Self::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            J::Object(entries) => match special_form(&entries) {
+                Some(SpecialForm::Link(cid)) => Self::Link(
+                    cid.parse()
+                        .map_err(|err| JsonError::InvalidLink(cid.to_string(), err))?,
+                ),
+                Some(SpecialForm::Bytes(encoded)) => Self::Bytes(
+                    base64::decode_config(encoded, base64::STANDARD_NO_PAD)
+                        .map_err(|_| JsonError::InvalidBytes(encoded.to_string()))?,
+                ),
+                None => Self::Map(
+                    entries
+                        .into_iter()
+                        .map(|(k, v)| Ok((k, Self::try_from(v)?)))
+                        .collect::<Result<_, _>>()?,
+                ),
+            },
+        })
+    }
+}
+
+enum SpecialForm<'a> {
+    Link(&'a str),
+    Bytes(&'a str),
+}
+
+/// Recognizes the single-entry `{"/": ...}` objects DAG-JSON reserves for links and bytes
+fn special_form(entries: &serde_json::Map<String, serde_json::Value>) -> Option<SpecialForm<'_>> {
+    if entries.len() != 1 {
+        return None;
+    }
+    match entries.get("/")? {
+        serde_json::Value::String(cid) => Some(SpecialForm::Link(cid)),
+        serde_json::Value::Object(inner) if inner.len() == 1 => {
+            inner.get("bytes")?.as_str().map(SpecialForm::Bytes)
+        }
+        _ => None,
+    }
+}
+
+/// Converts to JSON following the DAG-JSON conventions for links and bytes
+impl TryFrom<&Value> for serde_json::Value {
+    type Error = JsonError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        use serde_json::{json, Value as J};
+
+        Ok(match value {
+            Value::Null => J::Null,
+            Value::Bool(b) => J::Bool(*b),
+            Value::Int(i) => J::from(*i),
+            Value::Float(x) => serde_json::Number::from_f64(*x)
+                .map(J::Number)
+                .ok_or(JsonError::NonFiniteFloat(*x))?,
+            Value::String(s) => J::String(s.clone()),
+            Value::Bytes(b) => {
+                json!({ "/": { "bytes": base64::encode_config(b, base64::STANDARD_NO_PAD) } })
+            }
+            Value::List(items) => {
+                J::Array(items.iter().map(Self::try_from).collect::<Result<_, _>>()?)
+            }
+            Value::Map(entries) => J::Object(
+                entries
+                    .iter()
+                    .map(|(k, v)| Ok((k.clone(), Self::try_from(v)?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Link(cid) => json!({ "/": cid.to_string() }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn ints_and_floats_are_distinct() {
+        assert_ne!(Value::Int(1), Value::Float(1.0));
+        assert_eq!(Value::Float(f64::NAN), Value::Float(f64::NAN));
+        assert!(Value::Int(i64::MAX) < Value::Float(f64::MIN));
+        assert!(Value::Null < Value::Bool(false));
+        assert_eq!(Value::Int(1).kind(), Some(RepresentationKind::Int));
+        assert_eq!(Value::Null.kind_name(), "null");
+    }
+
+    #[test]
+    fn converts_from_and_to_dag_json_conventions() {
+        let json = json!({
+            "n": null,
+            "b": true,
+            "i": -3,
+            "f": 1.5,
+            "s": "hi",
+            "bytes": { "/": { "bytes": "AP8" } },
+            "l": [1, 2.0],
+            "link": { "/": "bafyreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku" },
+            "not_a_link": { "/": "x", "y": 1 },
+        });
+
+        let value = Value::try_from(json.clone()).unwrap();
+        let map = match &value {
+            Value::Map(map) => map,
+            _ => panic!("expected a map"),
+        };
+        assert_eq!(map["bytes"], Value::Bytes(vec![0x00, 0xff]));
+        assert_eq!(map["i"], Value::Int(-3));
+        assert_eq!(
+            map["l"],
+            Value::List(vec![Value::Int(1), Value::Float(2.0)])
+        );
+        assert_eq!(
+            map["link"],
+            Value::Link(Cid::new_v1(crate::cid::DAG_CBOR, b""))
+        );
+        assert_eq!(map["not_a_link"].kind(), Some(RepresentationKind::Map));
+        assert_eq!(value.links().len(), 1);
+
+        assert_eq!(serde_json::Value::try_from(&value).unwrap(), json);
+    }
+
+    #[test]
+    fn rejects_what_cant_be_converted() {
+        assert_eq!(
+            Value::try_from(json!(u64::MAX)).unwrap_err().to_string(),
+            "integer 18446744073709551615 is out of range"
+        );
+        assert!(Value::try_from(json!({ "/": { "bytes": "!" } })).is_err());
+        assert!(matches!(
+            Value::try_from(json!({ "/": "bafyreih" })),
+            Err(JsonError::InvalidLink(_, CidError::Truncated))
+        ));
+        assert!(serde_json::Value::try_from(&Value::Float(f64::INFINITY)).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! The [unsigned varints](https://github.com/multiformats/unsigned-varint) multiformats use for codes and lengths
+
+/// Longest varint multiformats allow, enough for 63 bits
+const MAX_LEN: usize = 9;
+
+/// Appends `n` as an unsigned LEB128 varint
+pub(crate) fn write(mut n: u64, out: &mut Vec<u8>) {
+    loop {
+        let low = n.to_le_bytes()[0] & 0x7f;
+        n >>= 7;
+        if n == 0 {
+            out.push(low);
+            return;
+        }
+        out.push(low | 0x80);
+    }
+}
+
+/// Reads a minimally encoded varint from the start of `bytes`, returning it and the bytes after it
+pub(crate) fn read(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut n = 0;
+    for (i, &byte) in bytes.iter().enumerate().take(MAX_LEN) {
+        n |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            // a final zero byte after others would make the encoding longer than necessary
+            if byte == 0 && i > 0 {
+                return None;
+            }
+            return Some((n, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn roundtrips_minimal_varints() {
+        for &(n, encoded) in &[
+            (0, &[0x00][..]),
+            (0x7f, &[0x7f]),
+            (0x80, &[0x80, 0x01]),
+            (300, &[0xac, 0x02]),
+        ] {
+            let mut out = vec![];
+            write(n, &mut out);
+            assert_eq!(out, encoded);
+            assert_eq!(read(encoded), Some((n, &[][..])));
+        }
+
+        assert_eq!(read(&[0x81, 0x00]), None);
+        assert_eq!(read(&[0x80]), None);
+        assert_eq!(read(&[0xff; 10]), None);
+    }
+}